@@ -0,0 +1,40 @@
+//! Times mapping colors to the xterm 256 color palette, which `Color256` does for every cell.
+//!
+//! ```sh
+//! cargo bench -p charcoal-core --bench quantize
+//! ```
+
+use std::{hint::black_box, time::Instant};
+
+use charcoal_core::color_depth::nearest_256;
+
+/* Every fourth value of each channel, 64^3 colors spread over the whole cube */
+const STEP: usize = 4;
+const ROUNDS: u32 = 5;
+
+fn main() {
+    let colors: Vec<(u8, u8, u8)> = (0..=255u8)
+        .step_by(STEP)
+        .flat_map(|r| (0..=255u8).step_by(STEP).map(move |g| (r, g)))
+        .flat_map(|(r, g)| (0..=255u8).step_by(STEP).map(move |b| (r, g, b)))
+        .collect();
+
+    /* The best round is the one least disturbed by other processes */
+    let best = (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            for &(r, g, b) in &colors {
+                black_box(nearest_256(black_box(r), black_box(g), black_box(b)));
+            }
+            start.elapsed()
+        })
+        .min()
+        .expect("ROUNDS is not 0");
+    println!(
+        "nearest_256: {} colors in {:.2?}, {:.1} ns per color (best of {})",
+        colors.len(),
+        best,
+        best.as_nanos() as f64 / colors.len() as f64,
+        ROUNDS
+    );
+}
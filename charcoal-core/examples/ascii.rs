@@ -0,0 +1,25 @@
+//! Prints an image as colored ASCII art.
+//!
+//! ```sh
+//! cargo run -p charcoal-core --example ascii -- path/to/image.png
+//! ```
+
+use charcoal_core::{AsciiOptions, OutputFormat, image_to_ascii};
+
+fn main() {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("Usage: ascii <image>");
+        std::process::exit(1);
+    };
+    let img = image::open(&path).unwrap_or_else(|e| {
+        eprintln!("Can't open {}: {}", path, e);
+        std::process::exit(1);
+    });
+
+    let opts = AsciiOptions {
+        max_width: 100,
+        format: OutputFormat::Ansi,
+        ..AsciiOptions::default()
+    };
+    print!("{}", image_to_ascii(&img, &opts));
+}
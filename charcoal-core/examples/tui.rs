@@ -0,0 +1,37 @@
+//! Shows an image full screen with ratatui until a key is pressed.
+//!
+//! ```sh
+//! cargo run -p charcoal-core --example tui -- path/to/image.png
+//! ```
+
+use charcoal_core::{RenderOptions, render_to_buffer};
+use ratatui::crossterm::event::{self, Event};
+
+fn main() -> std::io::Result<()> {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("Usage: tui <image>");
+        std::process::exit(1);
+    };
+    let img = image::open(&path).unwrap_or_else(|e| {
+        eprintln!("Can't open {}: {}", path, e);
+        std::process::exit(1);
+    });
+
+    let mut terminal = ratatui::init();
+    let opts = RenderOptions::default();
+    let result = loop {
+        if let Err(e) = terminal.draw(|f| {
+            let area = f.area();
+            render_to_buffer(&img, area, f.buffer_mut(), &opts);
+        }) {
+            break Err(e);
+        }
+        match event::read() {
+            Ok(Event::Key(_)) => break Ok(()),
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    ratatui::restore();
+    result
+}
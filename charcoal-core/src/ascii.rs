@@ -0,0 +1,401 @@
+//! Converting images to ASCII art.
+
+use std::{fs, io::Write, path::Path};
+
+use image::{
+    DynamicImage, GenericImageView, Rgba, RgbaImage,
+    imageops::{self, FilterType},
+};
+
+/// Characters ordered from dark to bright.
+pub const DEFAULT_CHARSET: &str = " .:-=+*#%@";
+/// Terminal cells are roughly twice as tall as they are wide.
+pub const DEFAULT_CHAR_ASPECT: f32 = 0.5;
+pub const DEFAULT_MAX_WIDTH: u32 = 80;
+
+/// How the characters of the ASCII art are colored.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// No color at all.
+    #[default]
+    Plain,
+    /// A `<pre>` block with one colored `<span>` per character.
+    Html,
+    /// 24-bit ANSI escape codes, for printing to a terminal.
+    Ansi,
+}
+
+impl OutputFormat {
+    /// Parses `plain`, `html` or `ansi`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "plain" => Ok(OutputFormat::Plain),
+            "html" => Ok(OutputFormat::Html),
+            "ansi" => Ok(OutputFormat::Ansi),
+            _ => Err(format!("Unknown output format: {}", s)),
+        }
+    }
+}
+
+/// What to do when the image isn't 16:9.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CropMode {
+    /// Center-crop to 16:9.
+    #[default]
+    Crop169,
+    /// Keep the whole image and its aspect ratio.
+    PreserveAspect,
+    /// Fit the whole image into a 16:9 frame padded with spaces.
+    Letterbox,
+}
+
+impl CropMode {
+    /// Parses `16:9`, `none` or `letterbox`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "16:9" => Ok(CropMode::Crop169),
+            "none" => Ok(CropMode::PreserveAspect),
+            "letterbox" => Ok(CropMode::Letterbox),
+            _ => Err(format!("Unknown crop mode: {}", s)),
+        }
+    }
+}
+
+/// Settings for [`image_to_ascii`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsciiOptions {
+    /// Width of the output in characters, images narrower than this are not scaled up.
+    pub max_width: u32,
+    /// Height of the output in lines, the width shrinks to keep the aspect ratio.
+    pub max_height: Option<u32>,
+    /// Width of a character cell divided by its height.
+    pub char_aspect: f32,
+    /// Characters ordered from dark to bright.
+    pub charset: String,
+    pub format: OutputFormat,
+    pub crop: CropMode,
+    /// Filter used to scale the image to the output size.
+    pub resample: FilterType,
+}
+
+impl Default for AsciiOptions {
+    fn default() -> Self {
+        Self {
+            max_width: DEFAULT_MAX_WIDTH,
+            max_height: None,
+            char_aspect: DEFAULT_CHAR_ASPECT,
+            charset: DEFAULT_CHARSET.to_string(),
+            format: OutputFormat::default(),
+            crop: CropMode::default(),
+            resample: FilterType::Triangle,
+        }
+    }
+}
+
+/// Parses the name of a resampling filter: `nearest`, `triangle`, `catmull-rom`, `gaussian` or
+/// `lanczos3`.
+pub fn parse_resample(s: &str) -> Result<FilterType, String> {
+    match s {
+        "nearest" => Ok(FilterType::Nearest),
+        "triangle" => Ok(FilterType::Triangle),
+        "catmull-rom" => Ok(FilterType::CatmullRom),
+        "gaussian" => Ok(FilterType::Gaussian),
+        "lanczos3" => Ok(FilterType::Lanczos3),
+        _ => Err(format!("Unknown resampling filter: {}", s)),
+    }
+}
+
+/// Picks the character from `charset` (ordered dark to bright) matching the pixel's brightness.
+pub fn ramp_char(p: &Rgba<u8>, charset: &str) -> char {
+    let chars: Vec<char> = charset.chars().collect();
+    if chars.is_empty() {
+        return ' ';
+    }
+    let luma = 0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32;
+    let i = (luma / 255.0 * (chars.len() - 1) as f32).round() as usize;
+    chars[i]
+}
+
+/* Output size in characters, shrunk to fit both limits while keeping the aspect ratio */
+fn output_size(img_w: u32, img_h: u32, opts: &AsciiOptions) -> (u32, u32) {
+    fit_size(img_w, img_h, opts.max_width.min(img_w), opts)
+}
+
+fn fit_size(img_w: u32, img_h: u32, max_width: u32, opts: &AsciiOptions) -> (u32, u32) {
+    let aspect = opts.char_aspect;
+    let out_w = max_width.max(1);
+    let out_h = ((img_h as f32 / img_w as f32) * out_w as f32 * aspect).round() as u32;
+    match opts.max_height {
+        Some(max_h) if out_h > max_h => {
+            let out_w = (max_h as f32 / aspect * img_w as f32 / img_h as f32).round() as u32;
+            (out_w.max(1), max_h.max(1))
+        }
+        _ => (out_w, out_h.max(1)),
+    }
+}
+
+/// Cuts the largest 16:9 rectangle out of the center of the image.
+pub fn crop_to_16_9(img: &DynamicImage) -> DynamicImage {
+    let (w, h) = img.dimensions();
+    if w * 9 > h * 16 {
+        let crop_w = h * 16 / 9;
+        img.crop_imm((w - crop_w) / 2, 0, crop_w, h)
+    } else {
+        let crop_h = w * 9 / 16;
+        img.crop_imm(0, (h - crop_h) / 2, w, crop_h)
+    }
+}
+
+/// One character of the ASCII art and the color of the pixels it stands for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AsciiCell {
+    pub ch: char,
+    /// None for the padding around a letterboxed image.
+    pub color: Option<Rgba<u8>>,
+}
+
+/// The characters of the ASCII art before they are formatted, row by row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsciiGrid {
+    pub width: u32,
+    pub height: u32,
+    /// `width * height` cells, the first row comes first.
+    pub cells: Vec<AsciiCell>,
+}
+
+impl AsciiGrid {
+    /// Iterates over the rows of the grid, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[AsciiCell]> {
+        self.cells.chunks(self.width.max(1) as usize)
+    }
+}
+
+/// Converts an image to a grid of characters at most `max_width` wide and, if given, at most
+/// `max_height` tall. `opts.format` is ignored, see [`image_to_ascii`] for text output.
+pub fn ascii_grid(img: &DynamicImage, opts: &AsciiOptions) -> AsciiGrid {
+    let cropped;
+    let img = match opts.crop {
+        CropMode::Crop169 => {
+            cropped = crop_to_16_9(img);
+            &cropped
+        }
+        _ => img,
+    };
+    let (img_w, img_h) = img.dimensions();
+
+    /* With a letterbox the image is fit into a 16:9 grid of characters and centered in it */
+    let (grid_w, grid_h, out_w, out_h) = match opts.crop {
+        CropMode::Letterbox => {
+            let (frame_w, frame_h) = fit_size(16, 9, opts.max_width, opts);
+            let inner = AsciiOptions {
+                max_width: frame_w,
+                max_height: Some(frame_h),
+                ..opts.clone()
+            };
+            let (out_w, out_h) = output_size(img_w, img_h, &inner);
+            (frame_w, frame_h, out_w.min(frame_w), out_h.min(frame_h))
+        }
+        _ => {
+            let (out_w, out_h) = output_size(img_w, img_h, opts);
+            (out_w, out_h, out_w, out_h)
+        }
+    };
+    let small = img.resize_exact(out_w, out_h, opts.resample);
+    let (off_x, off_y) = ((grid_w - out_w) / 2, (grid_h - out_h) / 2);
+
+    let mut cells = Vec::with_capacity(grid_w as usize * grid_h as usize);
+    for y in 0..grid_h {
+        for x in 0..grid_w {
+            let (sx, sy) = (x.wrapping_sub(off_x), y.wrapping_sub(off_y));
+            cells.push(if sx >= out_w || sy >= out_h {
+                AsciiCell {
+                    ch: ' ',
+                    color: None,
+                }
+            } else {
+                let p = small.get_pixel(sx, sy);
+                AsciiCell {
+                    ch: ramp_char(&p, &opts.charset),
+                    color: Some(p),
+                }
+            });
+        }
+    }
+    AsciiGrid {
+        width: grid_w,
+        height: grid_h,
+        cells,
+    }
+}
+
+/// Crops and scales the image the same way [`ascii_grid`] does, but keeps it an image with one
+/// pixel per character. Set `char_aspect` to 1.0 to keep the proportions of the image. `width`
+/// and `height` replace the size computed from `max_width` and `max_height`, if only one is
+/// given the other follows the aspect ratio. A letterbox is padded with black.
+pub fn resize_image(
+    img: &DynamicImage,
+    opts: &AsciiOptions,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> DynamicImage {
+    let cropped;
+    let img = match opts.crop {
+        CropMode::Crop169 => {
+            cropped = crop_to_16_9(img);
+            &cropped
+        }
+        _ => img,
+    };
+    let (img_w, img_h) = img.dimensions();
+    /* Output lines per character of width */
+    let ratio = img_h as f32 / img_w as f32 * opts.char_aspect;
+    let explicit = match (width, height) {
+        (Some(w), Some(h)) => Some((w, h)),
+        (Some(w), None) => Some((w, (w as f32 * ratio).round().max(1.0) as u32)),
+        (None, Some(h)) => Some(((h as f32 / ratio).round().max(1.0) as u32, h)),
+        (None, None) => None,
+    };
+
+    if opts.crop != CropMode::Letterbox {
+        let (w, h) = explicit.unwrap_or_else(|| output_size(img_w, img_h, opts));
+        return img.resize_exact(w.max(1), h.max(1), opts.resample);
+    }
+    let (frame_w, frame_h) = explicit.unwrap_or_else(|| fit_size(16, 9, opts.max_width, opts));
+    let inner = AsciiOptions {
+        max_width: frame_w,
+        max_height: Some(frame_h),
+        ..opts.clone()
+    };
+    /* An explicit size is filled even if that means scaling up */
+    let (w, h) = match explicit {
+        Some(_) => fit_size(img_w, img_h, frame_w, &inner),
+        None => output_size(img_w, img_h, &inner),
+    };
+    let (w, h) = (w.min(frame_w), h.min(frame_h));
+    let mut frame = RgbaImage::from_pixel(frame_w.max(1), frame_h.max(1), Rgba([0, 0, 0, 255]));
+    let small = img.resize_exact(w, h, opts.resample).to_rgba8();
+    imageops::overlay(
+        &mut frame,
+        &small,
+        ((frame_w - w) / 2) as i64,
+        ((frame_h - h) / 2) as i64,
+    );
+    DynamicImage::ImageRgba8(frame)
+}
+
+/* Charsets and watermarks may contain characters that HTML would read as markup. */
+fn html_escape(c: char) -> String {
+    match c {
+        '&' => "&amp;".to_string(),
+        '<' => "&lt;".to_string(),
+        '>' => "&gt;".to_string(),
+        '"' => "&quot;".to_string(),
+        c => c.to_string(),
+    }
+}
+
+/// Converts an image to ASCII art at most `max_width` characters wide and, if given, at most
+/// `max_height` lines tall. Every line ends with a newline.
+pub fn image_to_ascii(img: &DynamicImage, opts: &AsciiOptions) -> String {
+    grid_to_ascii(&ascii_grid(img, opts), opts.format)
+}
+
+/// Formats a grid from [`ascii_grid`] as text, e.g. after characters were written over it.
+/// Every line ends with a newline.
+pub fn grid_to_ascii(grid: &AsciiGrid, format: OutputFormat) -> String {
+    let mut out = String::new();
+    if format == OutputFormat::Html {
+        out.push_str("<pre style=\"background:#000\">\n");
+    }
+    for row in grid.rows() {
+        for cell in row {
+            let c = cell.ch;
+            let Some(Rgba([r, g, b, _])) = cell.color else {
+                match format {
+                    OutputFormat::Html => out.push_str(&html_escape(c)),
+                    _ => out.push(c),
+                }
+                continue;
+            };
+            match format {
+                OutputFormat::Plain => out.push(c),
+                OutputFormat::Html => out.push_str(&format!(
+                    "<span style=\"color:rgb({},{},{})\">{}</span>",
+                    r,
+                    g,
+                    b,
+                    html_escape(c)
+                )),
+                OutputFormat::Ansi => out.push_str(&format!("\x1b[38;2;{};{};{}m{}", r, g, b, c)),
+            }
+        }
+        if format == OutputFormat::Ansi {
+            out.push_str("\x1b[0m");
+        }
+        out.push('\n');
+    }
+    if format == OutputFormat::Html {
+        out.push_str("</pre>\n");
+    }
+    out
+}
+
+/// Writes the converted image to `output`, or to stdout if no file is given.
+pub fn write_ascii_output(ascii: &str, output: Option<&Path>) -> std::io::Result<()> {
+    match output {
+        Some(path) => fs::write(path, ascii)?,
+        None => std::io::stdout().write_all(ascii.as_bytes())?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn no_crop_keeps_a_square_square() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::new(100, 100));
+        let opts = AsciiOptions {
+            max_width: 40,
+            char_aspect: 0.45,
+            crop: CropMode::PreserveAspect,
+            ..AsciiOptions::default()
+        };
+        let grid = ascii_grid(&img, &opts);
+        assert_eq!(grid.width, 40);
+        assert_eq!(grid.height, (40.0 * opts.char_aspect).round() as u32);
+    }
+
+    #[test]
+    fn crops_to_16_9_by_default() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::new(100, 100));
+        let opts = AsciiOptions {
+            max_width: 32,
+            char_aspect: 1.0,
+            ..AsciiOptions::default()
+        };
+        assert_eq!(ascii_grid(&img, &opts).height, 18);
+    }
+
+    #[test]
+    fn html_escapes_markup_characters() {
+        let grid = AsciiGrid {
+            width: 4,
+            height: 1,
+            cells: ['<', '&', '"', '>']
+                .into_iter()
+                .enumerate()
+                .map(|(i, ch)| AsciiCell {
+                    ch,
+                    /* The padding of a letterbox has no color, it must be escaped too */
+                    color: (i % 2 == 0).then_some(Rgba([1, 2, 3, 255])),
+                })
+                .collect(),
+        };
+        let html = grid_to_ascii(&grid, OutputFormat::Html);
+        assert!(html.contains("<span style=\"color:rgb(1,2,3)\">&lt;</span>&amp;"));
+        assert!(html.contains("<span style=\"color:rgb(1,2,3)\">&quot;</span>&gt;"));
+    }
+}
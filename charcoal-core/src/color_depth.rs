@@ -0,0 +1,122 @@
+//! Reducing colors to what a terminal can show.
+
+#[cfg(feature = "tui")]
+use ratatui::style::Color;
+
+/// How many colors the terminal can show.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ColorDepth {
+    /// 24-bit RGB.
+    #[default]
+    TrueColor,
+    /// The xterm 256 color palette.
+    Color256,
+    /// The 16 ANSI colors.
+    Color16,
+    /// Only black and white.
+    Mono,
+}
+
+impl ColorDepth {
+    /// Parses `truecolor` (or `24bit`), `256`, `16` or `mono`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "truecolor" | "24bit" => Ok(ColorDepth::TrueColor),
+            "256" => Ok(ColorDepth::Color256),
+            "16" => Ok(ColorDepth::Color16),
+            "mono" => Ok(ColorDepth::Mono),
+            _ => Err(format!("Unknown color depth: {}", s)),
+        }
+    }
+
+    /// The closest color to `(r, g, b)` the terminal can display.
+    #[cfg(feature = "tui")]
+    pub fn color(&self, r: u8, g: u8, b: u8) -> Color {
+        match self {
+            ColorDepth::TrueColor => Color::Rgb(r, g, b),
+            ColorDepth::Color256 => Color::Indexed(nearest_256(r, g, b)),
+            ColorDepth::Color16 => ANSI_COLORS[nearest(&XTERM_PALETTE[..16 * 3], r, g, b)],
+            ColorDepth::Mono => {
+                /* Threshold the luma at 128 */
+                if 299 * r as u32 + 587 * g as u32 + 114 * b as u32 >= 128_000 {
+                    Color::White
+                } else {
+                    Color::Black
+                }
+            }
+        }
+    }
+}
+
+/* The 16 ANSI colors in palette order, their RGB values are the first 16 palette entries */
+#[cfg(feature = "tui")]
+const ANSI_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// The xterm 256 color palette as RGB triples: 16 system colors, a 6x6x6 cube and 24 grays.
+pub const XTERM_PALETTE: [u8; 256 * 3] = build_palette();
+
+const fn build_palette() -> [u8; 256 * 3] {
+    const SYSTEM: [u32; 16] = [
+        0x000000, 0x800000, 0x008000, 0x808000, 0x000080, 0x800080, 0x008080, 0xc0c0c0, 0x808080,
+        0xff0000, 0x00ff00, 0xffff00, 0x0000ff, 0xff00ff, 0x00ffff, 0xffffff,
+    ];
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    let mut palette = [0u8; 256 * 3];
+    let mut i = 0;
+    while i < 256 {
+        let (r, g, b) = if i < 16 {
+            let c = SYSTEM[i];
+            ((c >> 16) as u8, (c >> 8) as u8, c as u8)
+        } else if i < 232 {
+            let n = i - 16;
+            (LEVELS[n / 36], LEVELS[(n / 6) % 6], LEVELS[n % 6])
+        } else {
+            let v = (8 + (i - 232) * 10) as u8;
+            (v, v, v)
+        };
+        palette[i * 3] = r;
+        palette[i * 3 + 1] = g;
+        palette[i * 3 + 2] = b;
+        i += 1;
+    }
+    palette
+}
+
+/// Index of the palette entry with the smallest Euclidean distance to `(r, g, b)`.
+pub fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    nearest(&XTERM_PALETTE, r, g, b) as u8
+}
+
+fn nearest(palette: &[u8], r: u8, g: u8, b: u8) -> usize {
+    let mut best = 0;
+    let mut best_dist = u32::MAX;
+    for (i, entry) in palette.chunks_exact(3).enumerate() {
+        let dr = entry[0] as i32 - r as i32;
+        let dg = entry[1] as i32 - g as i32;
+        let db = entry[2] as i32 - b as i32;
+        let dist = (dr * dr + dg * dg + db * db) as u32;
+        if dist < best_dist {
+            best = i;
+            best_dist = dist;
+        }
+    }
+    best
+}
@@ -0,0 +1,35 @@
+//! Rendering for [charcoal](https://github.com/EinSatzMitX/charcoal), usable on its own.
+//!
+//! Three outputs are supported:
+//!
+//! - [`image_to_ascii`] turns an image into ASCII art, as plain text, HTML or with ANSI colors.
+//! - [`render_to_buffer`] draws an image into a ratatui [`Buffer`](ratatui::buffer::Buffer),
+//!   two pixels per cell. This needs the `tui` feature, which is on by default.
+//! - [`ascii_grid`] gives the characters and their colors as data, which
+//!   [`structured`] writes as JSON or CSV.
+//!
+//! ```no_run
+//! use charcoal_core::{AsciiOptions, image_to_ascii};
+//!
+//! let img = image::open("photo.png").unwrap();
+//! print!("{}", image_to_ascii(&img, &AsciiOptions::default()));
+//! ```
+
+pub mod ascii;
+pub mod color_depth;
+#[cfg(feature = "tui")]
+pub mod render;
+pub mod sample;
+pub mod structured;
+
+pub use ascii::{
+    AsciiCell, AsciiGrid, AsciiOptions, CropMode, OutputFormat, ascii_grid, grid_to_ascii,
+    image_to_ascii,
+};
+pub use color_depth::ColorDepth;
+#[cfg(feature = "tui")]
+pub use render::{AlphaBackground, RenderOptions, Renderer, render_to_buffer};
+pub use sample::sample_bilinear;
+#[cfg(feature = "json")]
+pub use structured::JsonWriter;
+pub use structured::{DataFormat, write_csv};
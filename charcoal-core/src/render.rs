@@ -0,0 +1,205 @@
+//! Drawing images into ratatui buffers.
+
+use image::{DynamicImage, Rgba};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Position, Rect},
+};
+
+use crate::{
+    ascii::{DEFAULT_CHARSET, ramp_char},
+    color_depth::ColorDepth,
+    sample::sample_bilinear,
+};
+
+/// How image pixels are turned into terminal cells.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Renderer {
+    /// Two pixels per cell using '▀' with separate fore- and background colors.
+    #[default]
+    HalfBlock,
+    /// One colored ASCII character per cell, for terminals without unicode.
+    Ascii,
+    /// The whole image as a PNG through the inline image protocol of iTerm2 and WezTerm. The
+    /// cells are only cleared here, sending the image is up to the caller.
+    #[cfg_attr(feature = "serde", serde(rename = "iterm2"))]
+    ITerm2,
+}
+
+impl Renderer {
+    /// Parses `half-block`, `ascii` or `iterm2`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "half-block" => Ok(Renderer::HalfBlock),
+            "ascii" => Ok(Renderer::Ascii),
+            "iterm2" => Ok(Renderer::ITerm2),
+            _ => Err(format!("Unknown renderer: {}", s)),
+        }
+    }
+}
+
+/// What shows through transparent pixels.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum AlphaBackground {
+    /// Light and dark gray cells, alternating like in image editors.
+    #[default]
+    Checkerboard,
+    Black,
+    White,
+    /// A solid color as `(r, g, b)`.
+    Color(u8, u8, u8),
+}
+
+const CHECKER_LIGHT: u8 = 204;
+const CHECKER_DARK: u8 = 153;
+
+impl AlphaBackground {
+    /// Parses `checkerboard`, `black`, `white` or `color:<hex>` with a hex code like `#1e1e2e`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "checkerboard" => Ok(AlphaBackground::Checkerboard),
+            "black" => Ok(AlphaBackground::Black),
+            "white" => Ok(AlphaBackground::White),
+            _ => {
+                let hex = s
+                    .strip_prefix("color:")
+                    .ok_or_else(|| format!("Unknown alpha background: {}", s))?;
+                let hex = hex.strip_prefix('#').unwrap_or(hex);
+                let channel = |i: usize| {
+                    hex.get(i..i + 2)
+                        .and_then(|c| u8::from_str_radix(c, 16).ok())
+                };
+                match (hex.len(), channel(0), channel(2), channel(4)) {
+                    (6, Some(r), Some(g), Some(b)) => Ok(AlphaBackground::Color(r, g, b)),
+                    _ => Err(format!("Invalid hex color: {}", hex)),
+                }
+            }
+        }
+    }
+
+    /// The background behind the image cell at (`cell_x`, `cell_y`).
+    fn color_at(&self, cell_x: u32, cell_y: u32) -> [u8; 3] {
+        match *self {
+            AlphaBackground::Checkerboard if (cell_x + cell_y).is_multiple_of(2) => {
+                [CHECKER_LIGHT; 3]
+            }
+            AlphaBackground::Checkerboard => [CHECKER_DARK; 3],
+            AlphaBackground::Black => [0; 3],
+            AlphaBackground::White => [255; 3],
+            AlphaBackground::Color(r, g, b) => [r, g, b],
+        }
+    }
+}
+
+/* `alpha * fg + (1 - alpha) * bg` per channel */
+fn blend(pixel: Rgba<u8>, bg: [u8; 3]) -> Rgba<u8> {
+    let alpha = pixel[3] as u16;
+    let mix =
+        |c: usize| ((pixel[c] as u16 * alpha + bg[c] as u16 * (255 - alpha) + 127) / 255) as u8;
+    Rgba([mix(0), mix(1), mix(2), 255])
+}
+
+/// Settings for [`render_to_buffer`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RenderOptions {
+    pub renderer: Renderer,
+    pub color_depth: ColorDepth,
+    /// The part of the image to draw as `(x, y, width, height)` in pixels, or the whole image
+    /// for `None`.
+    pub region: Option<(u32, u32, u32, u32)>,
+    /// Blends transparent pixels onto this, or ignores the alpha channel for `None`.
+    pub alpha_background: Option<AlphaBackground>,
+}
+
+/// Draws `img` centered into `area` of `buf`, scaled to fit while keeping its aspect ratio.
+/// Cells of `area` not covered by the image are reset. Returns the cells the image covers.
+pub fn render_to_buffer(
+    img: &DynamicImage,
+    area: Rect,
+    buf: &mut Buffer,
+    opts: &RenderOptions,
+) -> Rect {
+    let (src_x0, src_y0, src_w, src_h) = opts.region.unwrap_or((0, 0, img.width(), img.height()));
+    let term_cells_w = area.width as u32;
+    let term_cells_h = area.height as u32;
+    let term_pix_w = term_cells_w;
+    let term_pix_h = term_cells_h * 2;
+
+    // 1) same uniform scale to fit that rect into terminal pix
+    let scale_x = term_pix_w as f32 / src_w as f32;
+    let scale_y = term_pix_h as f32 / src_h as f32;
+    let scale = scale_x.min(scale_y);
+
+    let scaled_w = (src_w as f32 * scale).round() as u32;
+    let scaled_h = (src_h as f32 * scale).round() as u32;
+    let scaled_cells_w = scaled_w;
+    let scaled_cells_h = scaled_h.div_ceil(2);
+
+    // 2) center offsets
+    let offset_x = ((term_cells_w as i32 - scaled_cells_w as i32) / 2).max(0) as u32;
+    let offset_y = ((term_cells_h as i32 - scaled_cells_h as i32) / 2).max(0) as u32;
+
+    // clear letterbox
+    for y in 0..term_cells_h {
+        for x in 0..term_cells_w {
+            let pos = Position::new(area.x + x as u16, area.y + y as u16);
+            buf[pos].reset();
+        }
+    }
+
+    let drawn = Rect::new(
+        area.x + offset_x as u16,
+        area.y + offset_y as u16,
+        scaled_cells_w as u16,
+        scaled_cells_h as u16,
+    );
+    if opts.renderer == Renderer::ITerm2 {
+        return drawn;
+    }
+
+    // 3) draw each cell sampling from the zoomed rect
+    for cell_y in 0..scaled_cells_h {
+        for cell_x in 0..scaled_cells_w {
+            let tx = offset_x + cell_x;
+            let ty = offset_y + cell_y;
+
+            /* Centers of the sampled pixels, in source image coordinates */
+            let fx = (cell_x as f32 + 0.5) * src_w as f32 / scaled_w as f32;
+            let fy_top = (cell_y as f32 * 2.0 + 0.5) * src_h as f32 / scaled_h as f32;
+            let fy_bot = (cell_y as f32 * 2.0 + 1.5) * src_h as f32 / scaled_h as f32;
+
+            let img_x = src_x0 as f32 + fx - 0.5;
+            let top_y = src_y0 as f32 + fy_top - 0.5;
+            let bot_y = src_y0 as f32 + fy_bot - 0.5;
+
+            let mut pixel_top = sample_bilinear(img, img_x, top_y);
+            let mut pixel_bot = sample_bilinear(img, img_x, bot_y);
+            if let Some(background) = opts.alpha_background {
+                let bg = background.color_at(cell_x, cell_y);
+                pixel_top = blend(pixel_top, bg);
+                pixel_bot = blend(pixel_bot, bg);
+            }
+
+            let pos = Position::new(area.x + tx as u16, area.y + ty as u16);
+            let cell = &mut buf[pos];
+            let depth = opts.color_depth;
+            match opts.renderer {
+                Renderer::HalfBlock => {
+                    cell.set_char('▀')
+                        .set_fg(depth.color(pixel_top[0], pixel_top[1], pixel_top[2]))
+                        .set_bg(depth.color(pixel_bot[0], pixel_bot[1], pixel_bot[2]));
+                }
+                Renderer::Ascii => {
+                    let avg = |c: usize| ((pixel_top[c] as u16 + pixel_bot[c] as u16) / 2) as u8;
+                    let pixel = Rgba([avg(0), avg(1), avg(2), 255]);
+                    cell.set_char(ramp_char(&pixel, DEFAULT_CHARSET))
+                        .set_fg(depth.color(pixel[0], pixel[1], pixel[2]));
+                }
+                Renderer::ITerm2 => unreachable!(),
+            }
+        }
+    }
+    drawn
+}
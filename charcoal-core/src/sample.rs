@@ -0,0 +1,48 @@
+//! Reading pixels at fractional coordinates.
+
+use image::{DynamicImage, GenericImageView, Rgba};
+
+/// Blends the four pixels around `(x, y)`, weighted by how close they are. Integer coordinates
+/// are pixel centers, so `(0.5, 0.5)` is the average of the top-left 2x2 block. Coordinates
+/// outside the image are clamped to its edge. An empty image gives transparent black.
+pub fn sample_bilinear(img: &DynamicImage, x: f32, y: f32) -> Rgba<u8> {
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let x = x.clamp(0.0, (w - 1) as f32);
+    let y = y.clamp(0.0, (h - 1) as f32);
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(w - 1), (y0 + 1).min(h - 1));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+    let (p00, p10) = (img.get_pixel(x0, y0), img.get_pixel(x1, y0));
+    let (p01, p11) = (img.get_pixel(x0, y1), img.get_pixel(x1, y1));
+    Rgba(std::array::from_fn(|c| {
+        let top = p00[c] as f32 * (1.0 - fx) + p10[c] as f32 * fx;
+        let bottom = p01[c] as f32 * (1.0 - fx) + p11[c] as f32 * fx;
+        (top * (1.0 - fy) + bottom * fy).round() as u8
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn middle_of_checkerboard_is_the_mean() {
+        let img = RgbaImage::from_fn(2, 2, |x, y| match (x + y) % 2 {
+            0 => Rgba([0, 0, 0, 255]),
+            _ => Rgba([200, 100, 50, 255]),
+        });
+        let img = DynamicImage::ImageRgba8(img);
+        assert_eq!(sample_bilinear(&img, 0.5, 0.5), Rgba([100, 50, 25, 255]));
+    }
+
+    #[test]
+    fn empty_image_does_not_panic() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::new(0, 3));
+        assert_eq!(sample_bilinear(&img, 1.0, 1.0), Rgba([0, 0, 0, 0]));
+    }
+}
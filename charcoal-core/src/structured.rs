@@ -0,0 +1,187 @@
+//! ASCII art as data for other programs, in JSON or CSV.
+
+use std::io::{self, Write};
+
+use crate::ascii::AsciiGrid;
+
+/// How [`AsciiGrid`]s are written for other programs to read.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum DataFormat {
+    /// The usual ASCII art, see [`OutputFormat`](crate::OutputFormat).
+    #[default]
+    Text,
+    /// `{"width":W,"height":H,"rows":[...]}`, needs the `json` feature.
+    Json,
+    /// One line per character with its position and color.
+    Csv,
+}
+
+impl DataFormat {
+    /// Parses `text`, `json` or `csv`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "text" => Ok(DataFormat::Text),
+            "json" => Ok(DataFormat::Json),
+            "csv" => Ok(DataFormat::Csv),
+            _ => Err(format!("Unknown format: {}", s)),
+        }
+    }
+}
+
+/// Writes grids as JSON objects, either with every row as a string or with every character
+/// and its color as an object of its own.
+#[cfg(feature = "json")]
+pub struct JsonWriter<W: Write> {
+    out: W,
+}
+
+#[cfg(feature = "json")]
+#[derive(serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+struct Json<Row> {
+    width: u32,
+    height: u32,
+    rows: Vec<Row>,
+}
+
+#[cfg(feature = "json")]
+#[derive(serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+struct JsonCell {
+    char: char,
+    r: u8,
+    g: u8,
+    b: u8,
+}
+
+#[cfg(feature = "json")]
+impl<W: Write> JsonWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    /// Writes `{"width":W,"height":H,"rows":["row0","row1",...]}`.
+    pub fn write_mono(&mut self, grid: &AsciiGrid) -> io::Result<()> {
+        let rows = grid
+            .rows()
+            .map(|row| row.iter().map(|cell| cell.ch).collect::<String>())
+            .collect();
+        self.write(&Json {
+            width: grid.width,
+            height: grid.height,
+            rows,
+        })
+    }
+
+    /// Writes `{"width":W,"height":H,"rows":[[{"char":".","r":120,"g":80,"b":40},...],...]}`.
+    /// Letterbox padding is black.
+    pub fn write_color(&mut self, grid: &AsciiGrid) -> io::Result<()> {
+        let rows = grid
+            .rows()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| {
+                        let [r, g, b, _] = cell.color.map_or([0; 4], |color| color.0);
+                        JsonCell {
+                            char: cell.ch,
+                            r,
+                            g,
+                            b,
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        self.write(&Json {
+            width: grid.width,
+            height: grid.height,
+            rows,
+        })
+    }
+
+    fn write(&mut self, json: &impl serde::Serialize) -> io::Result<()> {
+        serde_json::to_writer(&mut self.out, json)?;
+        writeln!(self.out)
+    }
+}
+
+/// Writes `grid` as CSV with a header, one line per character. With `color` every line also
+/// has the red, green and blue value of the character, 0 for letterbox padding.
+pub fn write_csv(out: &mut impl Write, grid: &AsciiGrid, color: bool) -> io::Result<()> {
+    if color {
+        writeln!(out, "x,y,char,r,g,b")?;
+    } else {
+        writeln!(out, "x,y,char")?;
+    }
+    for (y, row) in grid.rows().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            /* Quote characters that would otherwise end the field */
+            let ch = match cell.ch {
+                '"' => "\"\"\"\"".to_string(),
+                ',' | ' ' => format!("\"{}\"", cell.ch),
+                ch => ch.to_string(),
+            };
+            write!(out, "{},{},{}", x, y, ch)?;
+            if color {
+                let [r, g, b, _] = cell.color.map_or([0; 4], |color| color.0);
+                write!(out, ",{},{},{}", r, g, b)?;
+            }
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::{AsciiCell, AsciiOptions, OutputFormat, ascii_grid, grid_to_ascii};
+    use image::{DynamicImage, Rgba, RgbaImage};
+
+    fn gradient() -> AsciiGrid {
+        let img = RgbaImage::from_fn(64, 36, |x, y| Rgba([x as u8 * 4, y as u8 * 7, 90, 255]));
+        let opts = AsciiOptions {
+            max_width: 16,
+            ..AsciiOptions::default()
+        };
+        ascii_grid(&DynamicImage::ImageRgba8(img), &opts)
+    }
+
+    #[test]
+    fn color_json_renders_like_the_grid_it_came_from() {
+        let grid = gradient();
+        let mut out = Vec::new();
+        JsonWriter::new(&mut out).write_color(&grid).unwrap();
+
+        let json: Json<Vec<JsonCell>> = serde_json::from_slice(&out).unwrap();
+        let parsed = AsciiGrid {
+            width: json.width,
+            height: json.height,
+            cells: json
+                .rows
+                .into_iter()
+                .flatten()
+                .map(|cell| AsciiCell {
+                    ch: cell.char,
+                    color: Some(Rgba([cell.r, cell.g, cell.b, 255])),
+                })
+                .collect(),
+        };
+        assert_eq!(
+            grid_to_ascii(&parsed, OutputFormat::Ansi),
+            grid_to_ascii(&grid, OutputFormat::Ansi)
+        );
+    }
+
+    #[test]
+    fn mono_json_has_the_plain_rows() {
+        let grid = gradient();
+        let mut out = Vec::new();
+        JsonWriter::new(&mut out).write_mono(&grid).unwrap();
+
+        let json: Json<String> = serde_json::from_slice(&out).unwrap();
+        assert_eq!((json.width, json.height), (grid.width, grid.height));
+        let text: String = json.rows.iter().map(|row| format!("{}\n", row)).collect();
+        assert_eq!(text, grid_to_ascii(&grid, OutputFormat::Plain));
+    }
+}
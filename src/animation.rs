@@ -0,0 +1,221 @@
+/* Decoding animated images into a list of frames and their delays */
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+    time::Duration,
+};
+
+use image::{
+    AnimationDecoder, DynamicImage, ImageFormat, ImageReader, ImageResult, RgbaImage,
+    codecs::{gif::GifDecoder, webp::WebPDecoder},
+};
+
+/* Most browsers show frames with a (near) zero delay for 100 ms, do the same */
+const MIN_DELAY: Duration = Duration::from_millis(10);
+const ZERO_DELAY_REPLACEMENT: Duration = Duration::from_millis(100);
+
+pub type Frames = Vec<(DynamicImage, Duration)>;
+
+/* ISOBMFF brands used by HEIF files. AVIF shares the container and brands like mif1, but the
+ * image crate handles it, so files with one of the AVIF brands aren't HEIF here */
+const HEIF_BRANDS: &[&[u8; 4]] = &[
+    b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx", b"mif1",
+];
+const AVIF_BRANDS: &[&[u8; 4]] = &[b"avif", b"avis"];
+
+/* HEIF and AVIF files start with an `ftyp` box with the major brand, a minor version and then
+ * the compatible brands. Empty for other files */
+fn brands(path: &Path) -> Vec<[u8; 4]> {
+    let mut header = Vec::new();
+    if File::open(path)
+        .and_then(|file| file.take(256).read_to_end(&mut header))
+        .is_err()
+        || header.len() < 12
+        || &header[4..8] != b"ftyp"
+    {
+        return Vec::new();
+    }
+    let size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let compatible = header.get(16..size.min(header.len())).unwrap_or_default();
+    std::iter::once(&header[8..12])
+        .chain(compatible.chunks_exact(4))
+        .map(|brand| [brand[0], brand[1], brand[2], brand[3]])
+        .collect()
+}
+
+fn has_brand(brands: &[[u8; 4]], list: &[&[u8; 4]]) -> bool {
+    brands.iter().any(|brand| list.contains(&brand))
+}
+
+fn is_heif(path: &Path) -> bool {
+    let brands = brands(path);
+    has_brand(&brands, HEIF_BRANDS) && !has_brand(&brands, AVIF_BRANDS)
+}
+
+#[cfg(not(feature = "avif"))]
+fn is_avif(path: &Path) -> bool {
+    has_brand(&brands(path), AVIF_BRANDS)
+}
+
+/* SVG is text, so look for the root element near the start instead of a magic number */
+fn is_svg(path: &Path) -> bool {
+    if has_extension(path, &["svg"]) {
+        return true;
+    }
+    let mut header = [0u8; 256];
+    let Ok(n) = File::open(path).and_then(|mut file| file.read(&mut header)) else {
+        return false;
+    };
+    header[..n].windows(4).any(|w| w == b"<svg")
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/* The error for formats whose decoder is behind a cargo feature that wasn't enabled */
+#[cfg(not(all(feature = "avif", feature = "heif", feature = "svg", feature = "pdf")))]
+pub fn not_compiled(format: &str, feature: &str) -> image::ImageError {
+    image::ImageError::Unsupported(
+        image::error::ImageFormatHint::Name(format!(
+            "{} (charcoal was built without the {} feature)",
+            format, feature
+        ))
+        .into(),
+    )
+}
+
+/// Whether `path` looks like an image charcoal can open, going by its extension
+pub fn is_supported(path: &Path) -> bool {
+    ImageFormat::from_path(path).is_ok()
+        || (cfg!(feature = "heif") && has_extension(path, &["heic", "heif"]))
+        || (cfg!(feature = "svg") && has_extension(path, &["svg"]))
+}
+
+/// Decodes the image at `path`. Animations also return all their frames, for still images the
+/// list is empty
+pub fn decode(path: &Path) -> ImageResult<(DynamicImage, Frames)> {
+    if is_heif(path) {
+        #[cfg(feature = "heif")]
+        return Ok((crate::heif::decode(path)?, Vec::new()));
+        #[cfg(not(feature = "heif"))]
+        return Err(not_compiled("HEIF", "heif"));
+    }
+    /* The image crate knows AVIF without the feature too, but can't decode it */
+    #[cfg(not(feature = "avif"))]
+    if is_avif(path) {
+        return Err(not_compiled("AVIF", "avif"));
+    }
+    if is_svg(path) {
+        #[cfg(feature = "svg")]
+        return Ok((crate::svg::decode(path)?, Vec::new()));
+        #[cfg(not(feature = "svg"))]
+        return Err(not_compiled("SVG", "svg"));
+    }
+    /* Guessed from the magic bytes, `RIFF....WEBP` for WebP, so the extension doesn't matter */
+    let reader = ImageReader::open(path)?.with_guessed_format()?;
+    if reader.format() == Some(ImageFormat::Gif) {
+        let decoder = GifDecoder::new(BufReader::new(File::open(path)?))?;
+        let frames = collect(decoder)?;
+        if frames.len() > 1 {
+            return Ok((frames[0].0.clone(), frames));
+        }
+    }
+    if reader.format() == Some(ImageFormat::WebP) {
+        let decoder = WebPDecoder::new(BufReader::new(File::open(path)?))?;
+        /* Still WebPs can't be read as frames, they are decoded like any other image below */
+        if decoder.has_animation() {
+            let frames = collect(decoder)?;
+            if frames.len() > 1 {
+                return Ok((frames[0].0.clone(), frames));
+            }
+        }
+    }
+    Ok((reader.decode()?, Vec::new()))
+}
+
+fn collect<'a>(decoder: impl AnimationDecoder<'a>) -> ImageResult<Frames> {
+    Ok(decoder
+        .into_frames()
+        .collect_frames()?
+        .into_iter()
+        .map(|frame| {
+            let delay = Duration::from(frame.delay());
+            let delay = if delay < MIN_DELAY {
+                ZERO_DELAY_REPLACEMENT
+            } else {
+                delay
+            };
+            (DynamicImage::ImageRgba8(frame.into_buffer()), delay)
+        })
+        .collect())
+}
+
+/// Averages `frames` with equal weights, they all have to be the same size
+pub fn blend(frames: &VecDeque<DynamicImage>) -> DynamicImage {
+    let Some(first) = frames.front() else {
+        return DynamicImage::default();
+    };
+    let mut sums = vec![0u32; first.width() as usize * first.height() as usize * 4];
+    for frame in frames {
+        for (sum, &v) in sums.iter_mut().zip(frame.to_rgba8().as_raw()) {
+            *sum += v as u32;
+        }
+    }
+    let n = frames.len() as u32;
+    let pixels = sums.iter().map(|&sum| ((sum + n / 2) / n) as u8).collect();
+    DynamicImage::ImageRgba8(
+        RgbaImage::from_raw(first.width(), first.height(), pixels).expect("buffer size matches"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn decodes_every_frame_of_an_animated_webp() {
+        /* Two 40x20 frames shown for 500 ms each, red and then blue */
+        let (first, frames) = decode(&fixture("anim.webp")).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!((first.width(), first.height()), (40, 20));
+        for (_, delay) in &frames {
+            assert_eq!(*delay, Duration::from_millis(500));
+        }
+        /* The frames are lossy, so only close to pure red and blue */
+        let [r, _, b, _] = frames[0].0.to_rgba8().get_pixel(20, 10).0;
+        assert!(r > 240 && b < 15);
+        let [r, _, b, _] = frames[1].0.to_rgba8().get_pixel(20, 10).0;
+        assert!(b > 240 && r < 15);
+    }
+
+    #[test]
+    fn only_heif_brands_go_to_libheif() {
+        assert!(is_heif(&fixture("tiny.heic")));
+        assert!(!is_heif(&fixture("tiny.avif")));
+    }
+
+    #[test]
+    fn avif_with_a_heif_major_brand_stays_avif() {
+        /* Some encoders put mif1 first and avif among the compatible brands */
+        let mut avif = std::fs::read(fixture("tiny.avif")).unwrap();
+        assert_eq!(&avif[8..20], b"avif\0\0\0\0mif1");
+        avif[8..12].copy_from_slice(b"mif1");
+        avif[16..20].copy_from_slice(b"avif");
+        let path = std::env::temp_dir().join(format!("charcoal-mif1-{}.avif", std::process::id()));
+        std::fs::write(&path, avif).unwrap();
+        let heif = is_heif(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(!heif);
+    }
+}
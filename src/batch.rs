@@ -0,0 +1,165 @@
+/* Converting or renaming a whole directory tree of images without opening the viewer */
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use charcoal_core::{AsciiOptions, OutputFormat, ascii};
+use color_eyre::eyre::{Result, eyre};
+use rayon::prelude::*;
+
+use crate::{animation, exif, filters::Filter};
+
+/* Names of the --ext values, which are also the extension of the files written */
+pub const EXTENSIONS: [&str; 3] = ["ascii", "html", "ansi"];
+
+/* Every image below `dir`, sorted so the progress lines come out in a predictable order */
+fn find_images(dir: &Path, images: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_images(&path, images)?;
+        } else if animation::is_supported(&path) {
+            images.push(path);
+        }
+    }
+    Ok(())
+}
+
+/* Where the ASCII art of `path` goes, subdirectories of the input are kept so files with the
+ * same stem in different directories don't overwrite each other */
+fn output_path(path: &Path, input_dir: &Path, output_dir: &Path, ext: &str) -> PathBuf {
+    let relative = path.strip_prefix(input_dir).unwrap_or(path);
+    output_dir.join(relative).with_extension(ext)
+}
+
+fn convert(path: &Path, out: &Path, filters: &[Filter], opts: &AsciiOptions) -> Result<()> {
+    /* Animations are converted by their first frame */
+    let (mut image, _) = animation::decode(path)?;
+    for filter in filters {
+        image = filter.apply(&image);
+    }
+    if let Some(dir) = out.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    ascii::write_ascii_output(&ascii::image_to_ascii(&image, opts), Some(out))?;
+    Ok(())
+}
+
+/// Converts every image below `input_dir` in parallel and writes the results to `output_dir`
+/// with the extension `ext`. Files that fail are reported and written to `error_log`, but don't
+/// stop the others. Returns how many failed
+pub fn convert_dir(
+    input_dir: &Path,
+    output_dir: &Path,
+    ext: &str,
+    filters: &[Filter],
+    opts: &AsciiOptions,
+    error_log: Option<&Path>,
+) -> Result<usize> {
+    let mut images = Vec::new();
+    find_images(input_dir, &mut images)?;
+    if images.is_empty() {
+        return Err(eyre!("No images found in {}", input_dir.display()));
+    }
+    images.sort();
+
+    let opts = AsciiOptions {
+        format: match ext {
+            "html" => OutputFormat::Html,
+            "ansi" => OutputFormat::Ansi,
+            _ => OutputFormat::Plain,
+        },
+        ..opts.clone()
+    };
+    let done = AtomicUsize::new(0);
+    let errors = Mutex::new(Vec::new());
+    images.par_iter().for_each(|path| {
+        let out = output_path(path, input_dir, output_dir, ext);
+        let result = convert(path, &out, filters, &opts);
+        let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+        match result {
+            Ok(()) => eprintln!(
+                "[{}/{}] {} -> {}",
+                n,
+                images.len(),
+                path.display(),
+                out.display()
+            ),
+            Err(e) => {
+                eprintln!("[{}/{}] {} failed: {}", n, images.len(), path.display(), e);
+                errors
+                    .lock()
+                    .unwrap()
+                    .push(format!("{}: {}", path.display(), e));
+            }
+        }
+    });
+
+    let mut errors = errors.into_inner().unwrap();
+    eprintln!(
+        "{} succeeded, {} failed",
+        images.len() - errors.len(),
+        errors.len()
+    );
+    if let Some(log) = error_log
+        && !errors.is_empty()
+    {
+        errors.sort();
+        let mut file = fs::File::create(log)?;
+        for error in &errors {
+            writeln!(file, "{}", error)?;
+        }
+    }
+    Ok(errors.len())
+}
+
+/// Puts the date the photo was taken in front of the name of every image below `dir`, e.g.
+/// `2024-05-01_14-30-00_IMG_1234.jpg`. Images without DateTimeOriginal, or that already start
+/// with their date, keep their name. With `dry_run` nothing is renamed, the changes are only
+/// printed. Reports how many were renamed and skipped at the end
+pub fn rename_by_date(dir: &Path, dry_run: bool) -> Result<()> {
+    let mut images = Vec::new();
+    find_images(dir, &mut images)?;
+    images.sort();
+
+    let (mut renamed, mut skipped) = (0, 0);
+    for path in images {
+        let date = exif::read(&path)
+            .ok()
+            .and_then(|exif| exif::date_taken(&exif));
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let Some(date) = date.filter(|date| !name.starts_with(date.as_str())) else {
+            skipped += 1;
+            continue;
+        };
+        let target = path.with_file_name(format!("{}_{}", date, name));
+        /* Never overwrite another photo taken in the same second */
+        if target.exists() {
+            eprintln!("{} skipped, {} exists", path.display(), target.display());
+            skipped += 1;
+            continue;
+        }
+        if !dry_run {
+            fs::rename(&path, &target)?;
+        }
+        eprintln!("{} -> {}", path.display(), target.display());
+        renamed += 1;
+    }
+    eprintln!(
+        "{} {}, {} skipped",
+        renamed,
+        if dry_run {
+            "would be renamed"
+        } else {
+            "renamed"
+        },
+        skipped
+    );
+    Ok(())
+}
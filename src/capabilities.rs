@@ -0,0 +1,164 @@
+use std::env;
+
+use charcoal_core::{ColorDepth, Renderer};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TerminalCapabilities {
+    pub true_color: bool,
+    pub sixel: bool,
+    pub kitty_protocol: bool,
+    /* The inline image protocol of iTerm2, which WezTerm understands as well */
+    pub iterm2: bool,
+    /* 0: ASCII only, 1: block elements like '▀', 2: full unicode */
+    pub unicode_level: u8,
+    pub color_depth: ColorDepth,
+}
+
+impl TerminalCapabilities {
+    /// The best renderer this terminal can display
+    pub fn best_renderer(&self) -> Renderer {
+        if self.iterm2 {
+            Renderer::ITerm2
+        } else if self.unicode_level >= 1 {
+            Renderer::HalfBlock
+        } else {
+            Renderer::Ascii
+        }
+    }
+
+    /// The most colors this terminal can display
+    pub fn best_color_depth(&self) -> ColorDepth {
+        self.color_depth
+    }
+}
+
+fn var(name: &str) -> String {
+    env::var(name).unwrap_or_default()
+}
+
+/// Guesses what the terminal supports from the environment, and asks the terminal itself
+/// whether it can display sixel graphics
+pub fn detect_capabilities() -> TerminalCapabilities {
+    let term = var("TERM");
+    let colorterm = var("COLORTERM");
+    let term_program = var("TERM_PROGRAM");
+    let kitty = env::var_os("KITTY_WINDOW_ID").is_some() || term == "xterm-kitty";
+    let vte_version: u32 = var("VTE_VERSION").parse().unwrap_or(0);
+    let modern_program = matches!(
+        term_program.as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty"
+    );
+
+    let true_color = colorterm == "truecolor"
+        || colorterm == "24bit"
+        || term.contains("direct")
+        || kitty
+        || modern_program
+        || vte_version >= 3600;
+
+    let locale = [var("LC_ALL"), var("LC_CTYPE"), var("LANG")]
+        .into_iter()
+        .find(|v| !v.is_empty())
+        .unwrap_or_default()
+        .to_lowercase();
+    let unicode_level = if term == "dumb" {
+        0
+    } else if kitty || modern_program || vte_version > 0 {
+        2
+    } else if !locale.is_empty()
+        && !locale.contains("utf-8")
+        && !locale.contains("utf8")
+        && locale != "c"
+        && locale != "posix"
+    {
+        /* Only give up on block elements for an explicit non-unicode locale */
+        0
+    } else {
+        1
+    };
+
+    /* Without true color, $TERM is the only hint left */
+    let color_depth = if true_color {
+        ColorDepth::TrueColor
+    } else if term.contains("256color") {
+        ColorDepth::Color256
+    } else if term.is_empty() || term == "dumb" {
+        ColorDepth::Mono
+    } else {
+        ColorDepth::Color16
+    };
+
+    TerminalCapabilities {
+        true_color,
+        color_depth,
+        sixel: term.contains("sixel") || query_sixel(),
+        kitty_protocol: kitty || term_program == "WezTerm" || term_program == "ghostty",
+        iterm2: matches!(term_program.as_str(), "iTerm.app" | "WezTerm"),
+        unicode_level,
+    }
+}
+
+/* Sends an XTSMGRAPHICS query for the sixel color registers and waits briefly for the answer.
+ * A DA1 request follows it, so terminals that ignore the first query still reply and we don't
+ * have to wait for the whole timeout */
+#[cfg(unix)]
+fn query_sixel() -> bool {
+    use std::{
+        io::{IsTerminal, Write},
+        time::{Duration, Instant},
+    };
+
+    use ratatui::crossterm::terminal;
+
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return false;
+    }
+    if terminal::enable_raw_mode().is_err() {
+        return false;
+    }
+
+    let mut stdout = std::io::stdout();
+    let _ = stdout.write_all(b"\x1b[?1;1;0S\x1b[c");
+    let _ = stdout.flush();
+
+    let mut response = Vec::new();
+    let deadline = Instant::now() + Duration::from_millis(200);
+    /* The DA1 reply looks like `ESC [ ? 62 ; 4 ; ... c` and always comes last */
+    while !response.ends_with(b"c") {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        let mut fds = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `fds` is a single valid pollfd and the read buffer outlives the call
+        let mut buf = [0u8; 64];
+        let n = unsafe {
+            if libc::poll(&mut fds, 1, remaining.as_millis() as i32) <= 0 {
+                break;
+            }
+            libc::read(libc::STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len())
+        };
+        if n <= 0 {
+            break;
+        }
+        response.extend_from_slice(&buf[..n as usize]);
+    }
+    let _ = terminal::disable_raw_mode();
+
+    let response = String::from_utf8_lossy(&response);
+    /* Status 0 in the XTSMGRAPHICS reply means success, attribute 4 in DA1 means sixel */
+    response.contains("\x1b[?1;0;")
+        || response
+            .split("\x1b[?")
+            .filter_map(|r| r.strip_suffix('c'))
+            .any(|params| params.split(';').any(|p| p == "4"))
+}
+
+#[cfg(not(unix))]
+fn query_sixel() -> bool {
+    false
+}
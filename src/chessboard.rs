@@ -0,0 +1,51 @@
+/* A chessboard test pattern, e.g. to calibrate a camera with */
+use image::{DynamicImage, Rgb, RgbImage};
+
+pub const DEFAULT_SIZE: (u32, u32) = (800, 800);
+pub const DEFAULT_COLORS: (Rgb<u8>, Rgb<u8>) = (Rgb([0, 0, 0]), Rgb([255, 255, 255]));
+
+/// Parses `black`, `white` or a hex code like `#1e1e2e`, the `#` is optional
+pub fn parse_color(s: &str) -> Result<Rgb<u8>, String> {
+    match s {
+        "black" => return Ok(DEFAULT_COLORS.0),
+        "white" => return Ok(DEFAULT_COLORS.1),
+        _ => {}
+    }
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+    };
+    match (hex.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(r), Some(g), Some(b)) => Ok(Rgb([r, g, b])),
+        _ => Err(format!("Expected black, white or a hex color: {}", s)),
+    }
+}
+
+/* `squares` by `squares` squares alternating between `colors`, drawn into a `width`x`height`
+ * image */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chessboard {
+    pub squares: u32,
+    pub width: u32,
+    pub height: u32,
+    pub colors: (Rgb<u8>, Rgb<u8>),
+}
+
+impl Chessboard {
+    /// Draws the board with the first color in the top left. Every square is `width / squares`
+    /// by `height / squares` pixels, so the image is cut down to a multiple of that
+    pub fn render(&self) -> DynamicImage {
+        let square_width = (self.width / self.squares).max(1);
+        let square_height = (self.height / self.squares).max(1);
+        let (width, height) = (square_width * self.squares, square_height * self.squares);
+        let img = RgbImage::from_fn(width, height, |x, y| {
+            if (x / square_width + y / square_height).is_multiple_of(2) {
+                self.colors.0
+            } else {
+                self.colors.1
+            }
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+}
@@ -0,0 +1,632 @@
+/* The command line as clap sees it. Every value is checked and converted here, so a mistake is
+ * reported with the flag it belongs to before anything is loaded */
+use std::{
+    fmt::Display,
+    ops::{
+        Bound::{Excluded, Included, Unbounded},
+        RangeBounds,
+    },
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+use charcoal_core::{
+    AlphaBackground, ColorDepth, CropMode, DataFormat, OutputFormat, Renderer, ascii,
+};
+use clap::{
+    ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueHint,
+    builder::{PossibleValuesParser, TypedValueParser},
+    error::ErrorKind,
+    parser::ValueSource,
+};
+use clap_complete::Shell;
+use image::{Rgb, imageops::FilterType};
+
+use crate::{
+    Computed, STDIN_PATH, batch, chessboard,
+    filters::{Filter, Gravity, MirrorAxis, SortAxis},
+    fractal::{self, FractalKind},
+    life,
+    noise::{self, NoiseType},
+    watermark::WatermarkPosition,
+};
+
+const AFTER_HELP: &str = "\
+Examples:
+  charcoal photo.png ~/Pictures        open an image and a directory, each in a tab
+  charcoal --cartoon 8 --vhs photo.png apply filters, in the order they are given
+  charcoal --output-format ansi --max-width 80 photo.png
+                                       print colored ASCII art instead
+  curl -s https://example.com/cat.jpg | charcoal -
+                                       show an image piped to stdin
+  charcoal --mandelbrot --mandelbrot-center -0.745,0.11 --mandelbrot-zoom 100
+                                       explore the Mandelbrot set
+  charcoal --noise perlin --noise-octaves 4 --resize-output texture.png
+                                       save a noise texture
+
+Signals (not on Windows):
+  SIGUSR1                              reload the current image
+  SIGUSR2                              show the next image";
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "charcoal",
+    version,
+    about = "View images in the terminal, or turn them into ASCII art",
+    after_help = AFTER_HELP,
+    args_override_self = true
+)]
+pub struct CliArgs {
+    /// Images or directories to open, each in its own tab. - reads from stdin
+    #[arg(value_name = "PATH", value_hint = ValueHint::AnyPath)]
+    pub paths: Vec<String>,
+
+    /// Apply the cartoon filter with k colors
+    #[arg(long, value_name = "k", allow_hyphen_values = true, value_parser = filter("cartoon"))]
+    pub cartoon: Vec<Filter>,
+    /// Degrade the image with JPEG artifacts (1-10)
+    #[arg(long, value_name = "quality", allow_hyphen_values = true, value_parser = filter("datamosh"))]
+    pub datamosh: Vec<Filter>,
+    /// Median filter, 1 is 3x3, 2 is 5x5 and so on
+    #[arg(long, value_name = "strength", allow_hyphen_values = true, value_parser = filter("denoise"))]
+    pub denoise: Vec<Filter>,
+    /// Show only one of R, G, B, A, H, S, V, Y, Cb or Cr
+    #[arg(long, value_name = "channel", allow_hyphen_values = true, value_parser = filter("channels"))]
+    pub channels: Vec<Filter>,
+    /// Equalize the histogram of all, R, G, B or Y
+    #[arg(long, value_name = "mode", allow_hyphen_values = true, value_parser = filter("equalize"))]
+    pub equalize: Vec<Filter>,
+    /// Adaptive histogram equalization in tiles
+    #[arg(long, num_args = 2, value_names = ["clip-limit", "tile-size"], allow_hyphen_values = true, value_parser = number_in((Excluded(0.0f32), Unbounded)))]
+    pub clahe: Vec<f32>,
+    /// Shift the hue by H degrees, scale saturation and lightness by S and L, e.g. 30:1.2:0.9
+    #[arg(long, value_name = "H:S:L", allow_hyphen_values = true, value_parser = hsl_adjust)]
+    pub hsl_adjust: Vec<Filter>,
+    /// Tint shadows and highlights with two hues
+    #[arg(long, num_args = 2, value_names = ["shadow-hue", "highlight-hue"], allow_hyphen_values = true, value_parser = finite)]
+    pub split_tone: Vec<f32>,
+    /// Simulate the artifacts of an old VHS tape
+    #[arg(long, action = ArgAction::Count)]
+    pub vhs: u8,
+    /// Curve, scanlines, phosphor stripes and glow of a CRT
+    #[arg(long, action = ArgAction::Count)]
+    pub crt: u8,
+    /// Repeat a wedge of the image n times around the center
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = filter("kaleidoscope"))]
+    pub kaleidoscope: Vec<Filter>,
+    /// Move red px pixels left and blue px pixels right
+    #[arg(long, value_name = "px", allow_hyphen_values = true, value_parser = filter("ca"))]
+    pub chromatic_aberration: Vec<Filter>,
+    /// Reduce every channel to n levels (2-8), after the other filters
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = filter("posterize"))]
+    pub posterize: Option<Filter>,
+    /// Pad the shorter side so the image is square, after the other filters
+    #[arg(long)]
+    pub pad_to_square: bool,
+    /// Where the image goes in the square (default center)
+    #[arg(long, value_name = "side", requires = "pad_to_square", value_parser = PossibleValuesParser::new(["center", "north", "south", "east", "west"]).try_map(|s| Gravity::parse(&s)))]
+    pub pad_gravity: Option<Gravity>,
+    /// Color of the padding: black, white or a hex code (default transparent)
+    #[arg(long, value_name = "color", requires = "pad_to_square", value_parser = chessboard::parse_color)]
+    pub pad_color: Option<Rgb<u8>>,
+    /// Make the corners transparent with this radius in pixels, after the padding
+    #[arg(long, value_name = "radius", allow_hyphen_values = true, value_parser = filter("round-corners"))]
+    pub round_corners: Option<Filter>,
+    /// Put a blurred shadow in black, white or a hex color behind the image, after the corners
+    #[arg(long, num_args = 4, value_names = ["x", "y", "blur", "color"], allow_hyphen_values = true)]
+    pub drop_shadow: Option<Vec<String>>,
+    /// Rebuild the image from the thumbnails in dir
+    #[arg(long, value_name = "dir", value_hint = ValueHint::DirPath)]
+    pub mosaic_tile: Option<PathBuf>,
+    /// Size of a mosaic tile in pixels (default 16)
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = number_in(1u32..))]
+    pub tile_px: Option<u32>,
+    /// Direction used by :sort-pixels
+    #[arg(long, value_name = "axis", value_parser = PossibleValuesParser::new(["row", "column"]).try_map(|s| SortAxis::parse(&s)))]
+    pub sort_axis: Option<SortAxis>,
+    /// Sort only pixels with a luma between low and high
+    #[arg(long, num_args = 2, value_names = ["low", "high"], allow_hyphen_values = true)]
+    pub pixel_sort_threshold: Option<Vec<u8>>,
+    /// Crop borders of a single color off the edges, before the other filters
+    #[arg(long)]
+    pub auto_crop_whitespace: bool,
+    /// How far a pixel may be from the border color and still be cropped (default 16)
+    #[arg(long, value_name = "n", requires = "auto_crop_whitespace", value_parser = number_in(0u8..))]
+    pub crop_threshold: Option<u8>,
+
+    /// Print ASCII art instead
+    #[arg(long, value_name = "fmt", value_parser = PossibleValuesParser::new(["plain", "html", "ansi"]).try_map(|s| OutputFormat::parse(&s)))]
+    pub output_format: Option<OutputFormat>,
+    /// Save the cropped and scaled image instead
+    #[arg(long, value_name = "file", value_hint = ValueHint::FilePath)]
+    pub resize_output: Option<PathBuf>,
+    /// Width for --resize-output, ignoring --max-width
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = number_in(1u32..))]
+    pub resize_width: Option<u32>,
+    /// Height for --resize-output, ignoring --max-height
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = number_in(1u32..))]
+    pub resize_height: Option<u32>,
+    /// Resampling filter (default triangle)
+    #[arg(long, value_name = "name", value_parser = PossibleValuesParser::new(["nearest", "triangle", "catmull-rom", "gaussian", "lanczos3"]).try_map(|s| ascii::parse_resample(&s)))]
+    pub filter: Option<FilterType>,
+    /// Original on the left, filtered on the right, e.g. "cartoon 8"
+    #[arg(long, value_name = "filter", allow_hyphen_values = true, value_parser = split_view)]
+    pub split_view: Option<Filter>,
+    /// Reflect the left half onto the right
+    #[arg(long)]
+    pub mirror_x: bool,
+    /// Reflect the top half onto the bottom
+    #[arg(long)]
+    pub mirror_y: bool,
+    /// Show the Mandelbrot set instead of an image
+    #[arg(long)]
+    pub mandelbrot: bool,
+    /// Show the Julia set of the constant cx + cy·i
+    #[arg(long, value_name = "cx,cy", allow_hyphen_values = true, value_parser = fractal::parse_point)]
+    pub julia: Option<(f64, f64)>,
+    /// Point of the complex plane in the middle, also for --julia
+    #[arg(long, value_name = "x,y", allow_hyphen_values = true, value_parser = fractal::parse_point)]
+    pub mandelbrot_center: Option<(f64, f64)>,
+    /// Magnification, 1 shows the whole set
+    #[arg(long, value_name = "z", allow_hyphen_values = true, value_parser = number_in((Excluded(0.0), Unbounded)))]
+    pub mandelbrot_zoom: Option<f64>,
+    /// Generate perlin, simplex or white noise instead of opening an image
+    #[arg(long, value_name = "type", value_parser = PossibleValuesParser::new(["perlin", "simplex", "white"]).try_map(|s| NoiseType::parse(&s)))]
+    pub noise: Option<NoiseType>,
+    /// Layers of ever finer detail on top of the noise (default 1)
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = number_in(1..=noise::MAX_OCTAVES))]
+    pub noise_octaves: Option<usize>,
+    /// Show a chessboard of n by n squares instead of an image
+    #[arg(long, value_name = "n", value_parser = number_in(1u32..))]
+    pub chessboard: Option<u32>,
+    /// Color of the top left square (default black)
+    #[arg(long, value_name = "color", value_parser = chessboard::parse_color)]
+    pub cb_color1: Option<Rgb<u8>>,
+    /// Color of the other squares (default white)
+    #[arg(long, value_name = "color", value_parser = chessboard::parse_color)]
+    pub cb_color2: Option<Rgb<u8>>,
+    /// Play Conway's Game of Life on a random grid of this size, e.g. 120x80
+    #[arg(long, value_name = "WxH", value_parser = life::parse_size)]
+    pub life: Option<(u32, u32)>,
+    /// Show an animated plasma effect instead of an image
+    #[arg(long)]
+    pub plasma: bool,
+    /// Width of the generated image (default 800, 320 for --plasma)
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = number_in(1u32..))]
+    pub width: Option<u32>,
+    /// Height of the generated image (default 600, 240 for --plasma, 800 for --chessboard)
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = number_in(1u32..))]
+    pub height: Option<u32>,
+    /// Behind transparent pixels: checkerboard (default), black, white or color:<hex>
+    #[arg(long, value_name = "bg", allow_hyphen_values = true, value_parser = AlphaBackground::parse)]
+    pub alpha_bg: Option<AlphaBackground>,
+    /// Print every EXIF field of the image as JSON
+    #[arg(long, requires = "paths")]
+    pub exif_dump: bool,
+    /// Never read or show the EXIF data of images, :write never saves metadata either way
+    #[arg(long, conflicts_with = "exif_dump")]
+    pub strip_exif: bool,
+    /// Turn the pixels the way the EXIF orientation says before saving with :write or
+    /// --resize-output, so the file looks right in apps that ignore the orientation
+    #[arg(long, conflicts_with = "strip_exif")]
+    pub rotate_exif: bool,
+    /// Show the colors of images with an ICC profile as they are stored, instead of converting
+    /// them to sRGB. Only builds with the icc feature convert them
+    #[arg(long)]
+    pub no_icc_convert: bool,
+    /// Open the images of a directory in the order they were taken, by EXIF DateTimeOriginal.
+    /// Images without a date come last
+    #[arg(long, conflicts_with = "strip_exif")]
+    pub sort_by_exif_date: bool,
+    /// Put the images without a date first with --sort-by-exif-date
+    #[arg(long, requires = "sort_by_exif_date")]
+    pub undated_first: bool,
+    /// Print the n most common colors as hex codes
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = number_in(1usize..))]
+    pub palette: Option<usize>,
+    /// Text, json or csv, colored with --output-format
+    #[arg(long, value_name = "fmt", value_parser = PossibleValuesParser::new(["text", "json", "csv"]).try_map(|s| DataFormat::parse(&s)))]
+    pub format: Option<DataFormat>,
+    /// Write the ASCII art to a file instead of stdout
+    #[arg(long, value_name = "file", value_hint = ValueHint::FilePath)]
+    pub output: Option<PathBuf>,
+    /// Width of the ASCII art in characters
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = number_in(1u32..))]
+    pub max_width: Option<u32>,
+    /// Width of a character cell divided by its height
+    #[arg(long, value_name = "ratio", allow_hyphen_values = true, value_parser = number_in((Excluded(0.0f32), Unbounded)))]
+    pub char_aspect: Option<f32>,
+    /// Characters used for ASCII art, dark to bright
+    #[arg(long, value_name = "chars", allow_hyphen_values = true)]
+    pub charset: Option<String>,
+    #[arg(long, value_name = "renderer", value_parser = PossibleValuesParser::new(["half-block", "ascii", "iterm2"]).try_map(|s| Renderer::parse(&s)))]
+    pub renderer: Option<Renderer>,
+    /// Detected by default
+    #[arg(long, value_name = "depth", value_parser = PossibleValuesParser::new(["truecolor", "24bit", "256", "16", "mono"]).try_map(|s| ColorDepth::parse(&s)))]
+    pub color_depth: Option<ColorDepth>,
+    /// Height of the ASCII art in lines
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = number_in(1u32..))]
+    pub max_height: Option<u32>,
+    /// Size the ASCII art to the current terminal
+    #[arg(long)]
+    pub fit_terminal: bool,
+    /// Cut the middle 16:9 out of the image (default), keep all of it or pad it to 16:9
+    #[arg(long, value_name = "mode", value_parser = PossibleValuesParser::new(["16:9", "none", "letterbox"]).try_map(|s| CropMode::parse(&s)))]
+    pub crop: Option<CropMode>,
+    /// Same as --crop none
+    #[arg(long, visible_alias = "preserve-aspect")]
+    pub no_crop: bool,
+    /// Same as --crop letterbox
+    #[arg(long)]
+    pub letterbox: bool,
+    /// Write text over the image, in big letters in ASCII art
+    #[arg(long, value_name = "text")]
+    pub watermark: Option<String>,
+    /// Where --watermark goes (default bottom-right)
+    #[arg(long, value_name = "position", requires = "watermark", value_parser = PossibleValuesParser::new(["top-left", "top-right", "bottom-left", "bottom-right", "center"]).try_map(|s| WatermarkPosition::parse(&s)))]
+    pub watermark_position: Option<WatermarkPosition>,
+    /// Rename every image below dir to YYYY-MM-DD_HH-MM-SS_<name> by its EXIF DateTimeOriginal
+    #[arg(long, value_name = "dir", value_hint = ValueHint::DirPath, conflicts_with = "strip_exif")]
+    pub batch_rename_exif: Option<PathBuf>,
+    /// Only print what --batch-rename-exif would rename
+    #[arg(long, requires = "batch_rename_exif")]
+    pub dry_run: bool,
+    /// Convert every image below --input-dir to ASCII art files in --output-dir
+    #[arg(long, requires_all = ["input_dir", "output_dir"])]
+    pub headless: bool,
+    #[arg(long, value_name = "dir", value_hint = ValueHint::DirPath)]
+    pub input_dir: Option<PathBuf>,
+    #[arg(long, value_name = "dir", value_hint = ValueHint::DirPath)]
+    pub output_dir: Option<PathBuf>,
+    /// Extension and format of the converted files
+    #[arg(long, value_name = "ext", default_value = "ascii", value_parser = batch::EXTENSIONS)]
+    pub ext: String,
+    /// Write the files that couldn't be converted to this file
+    #[arg(long, value_name = "path", value_hint = ValueHint::FilePath)]
+    pub error_log: Option<PathBuf>,
+
+    /// Show a specimen of a TrueType/OpenType font
+    #[arg(long, value_name = "path", value_hint = ValueHint::FilePath)]
+    pub preview_font: Option<PathBuf>,
+    /// Blend each frame of an animation with the last n
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = number_in(1usize..))]
+    pub frame_blend: Option<usize>,
+    /// Saturate muted colors, -1.0 to 1.0
+    #[arg(long, value_name = "amount", allow_hyphen_values = true, value_parser = number_in(-1.0f32..=1.0))]
+    pub vibrance: Option<f32>,
+    /// Add film grain, strength is the std dev in 0-255
+    #[arg(long, value_name = "strength", allow_hyphen_values = true, value_parser = number_in(0.0f32..))]
+    pub grain: Option<f32>,
+    /// Shift random rows sideways, a new pattern for every animation frame
+    #[arg(long)]
+    pub glitch: bool,
+    /// Chance of each row being shifted (default 0.05)
+    #[arg(long, value_name = "p", allow_hyphen_values = true, value_parser = number_in(0.0f32..=1.0))]
+    pub glitch_probability: Option<f32>,
+    /// Show another image on top at half opacity
+    #[arg(long, value_name = "path", value_hint = ValueHint::FilePath)]
+    pub onion_skin: Option<PathBuf>,
+    /// Show the per-pixel mean of all images in dir
+    #[arg(long, value_name = "dir", value_hint = ValueHint::DirPath)]
+    pub average: Option<PathBuf>,
+    /// Show the per-pixel standard deviation of dir
+    #[arg(long, value_name = "dir", value_hint = ValueHint::DirPath)]
+    pub stdev: Option<PathBuf>,
+    /// Open the file with this inode (Linux only)
+    #[arg(long, value_name = "dev:ino", allow_hyphen_values = true, value_parser = inode)]
+    pub inode: Vec<String>,
+    /// Show the next image after this many seconds
+    #[arg(long, value_name = "seconds", allow_hyphen_values = true, value_parser = slideshow_delay)]
+    pub slideshow: Option<Duration>,
+    /// Reload the image when the file changes
+    #[arg(long)]
+    pub watch: bool,
+    /// Start zoomed in on this region of the image, in pixels
+    #[arg(long, num_args = 4, value_names = ["x", "y", "w", "h"])]
+    pub focus_region: Option<Vec<u32>>,
+    /// Start panned to the center of mass of the brightest pixels
+    #[arg(long)]
+    pub center_of_mass: bool,
+    /// Start animations at frame n, counting from 0
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = number_in(0usize..))]
+    pub start_frame: Option<usize>,
+    /// Loop animations back to the start frame after frame n
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = number_in(0usize..))]
+    pub end_frame: Option<usize>,
+    /// Redraw at most n times per second (default 30)
+    #[arg(long, value_name = "n", allow_hyphen_values = true, value_parser = number_in(1u32..))]
+    pub fps: Option<u32>,
+    /// Zoom around the mouse pointer instead of the middle of the screen
+    #[arg(long)]
+    pub zoom_to_cursor: bool,
+    /// Read the image from stdin, same as the path -
+    #[arg(long, action = ArgAction::Count)]
+    pub pipe: u8,
+    /// Use another config file
+    #[arg(long, value_name = "path", value_hint = ValueHint::FilePath)]
+    pub config: Option<PathBuf>,
+    /// Print the active config as TOML and exit
+    #[arg(long)]
+    pub print_config: bool,
+    /// Print the detected terminal capabilities and exit
+    #[arg(long)]
+    pub debug_caps: bool,
+    /* Hidden on purpose, it's a tool for profiling the renderers */
+    #[arg(long, hide = true, value_name = "frames", allow_hyphen_values = true, value_parser = number_in(1usize..))]
+    pub benchmark: Option<usize>,
+    /* Hidden too, packagers call it once and install the result */
+    #[arg(long, hide = true, value_name = "shell")]
+    pub generate_completions: Option<Shell>,
+
+    /// What the app works out from the flags above once they are parsed
+    #[arg(skip)]
+    pub computed: Computed,
+}
+
+/// The command line, with everything that depends on the order of the flags worked out
+pub struct Parsed {
+    pub args: CliArgs,
+    /// The filters of the flags that can be given several times, in the order they were given
+    pub filters: Vec<Filter>,
+    /// The positional paths, - for --pipe and the files found by --inode, in the order they
+    /// were given
+    pub paths: Vec<String>,
+    /* Of the flags that set the same thing, the last one wins */
+    pub crop: CropMode,
+    pub mirror: Option<MirrorAxis>,
+    pub fractal: Option<FractalKind>,
+    pub drop_shadow: Option<Filter>,
+}
+
+/// Parses the command line, exiting with clap's message if something is wrong. Also prints the
+/// completions and exits for --generate-completions
+pub fn parse() -> Parsed {
+    let matches = CliArgs::command().get_matches();
+    let cli = CliArgs::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let Some(shell) = cli.generate_completions {
+        print_completions(shell);
+        std::process::exit(0);
+    }
+    if let Some(threshold) = &cli.pixel_sort_threshold
+        && threshold[0] > threshold[1]
+    {
+        invalid("--pixel-sort-threshold", "low can't be higher than high");
+    }
+
+    /* Filters are applied in the order they were given, whatever flag they come from */
+    let mut filters: Vec<(usize, Filter)> = Vec::new();
+    for (id, values) in [
+        ("cartoon", &cli.cartoon),
+        ("datamosh", &cli.datamosh),
+        ("denoise", &cli.denoise),
+        ("channels", &cli.channels),
+        ("equalize", &cli.equalize),
+        ("hsl_adjust", &cli.hsl_adjust),
+        ("kaleidoscope", &cli.kaleidoscope),
+        ("chromatic_aberration", &cli.chromatic_aberration),
+    ] {
+        for (i, filter) in occurrences(&matches, id, values, 1) {
+            filters.push((i, filter[0].clone()));
+        }
+    }
+    for (i, clahe) in occurrences(&matches, "clahe", &cli.clahe, 2) {
+        if clahe[1].fract() != 0.0 {
+            invalid("--clahe", "the tile size has to be a whole number");
+        }
+        filters.push((
+            i,
+            Filter::Clahe {
+                clip_limit: clahe[0],
+                tile_size: clahe[1] as u32,
+            },
+        ));
+    }
+    for (i, hues) in occurrences(&matches, "split_tone", &cli.split_tone, 2) {
+        filters.push((
+            i,
+            Filter::SplitTone {
+                shadow_hue: hues[0],
+                highlight_hue: hues[1],
+            },
+        ));
+    }
+    for (id, filter) in [("vhs", Filter::Vhs), ("crt", Filter::Crt)] {
+        for i in given_indices(&matches, id) {
+            filters.push((i, filter.clone()));
+        }
+    }
+    filters.sort_by_key(|(i, _)| *i);
+
+    /* The color is the only value that isn't a number, so the filter checks all four */
+    let drop_shadow = cli.drop_shadow.as_ref().map(|shadow| {
+        let shadow: Vec<&str> = shadow.iter().map(String::as_str).collect();
+        Filter::parse(&[&["drop-shadow"], shadow.as_slice()].concat())
+            .unwrap_or_else(|e| invalid("--drop-shadow", &e))
+    });
+
+    let mut paths: Vec<(usize, String)> = occurrences(&matches, "paths", &cli.paths, 1)
+        .chain(occurrences(&matches, "inode", &cli.inode, 1))
+        .map(|(i, path)| (i, path[0].clone()))
+        .collect();
+    for i in given_indices(&matches, "pipe") {
+        paths.push((i, STDIN_PATH.to_string()));
+    }
+    paths.sort_by_key(|(i, _)| *i);
+
+    let crop = [
+        ("crop", cli.crop),
+        ("no_crop", Some(CropMode::PreserveAspect)),
+        ("letterbox", Some(CropMode::Letterbox)),
+    ]
+    .into_iter()
+    .filter_map(|(id, mode)| Some((last_index(&matches, id)?, mode?)))
+    .max_by_key(|(i, _)| *i)
+    .map_or(CropMode::default(), |(_, mode)| mode);
+    let mirror = [("mirror_x", MirrorAxis::X), ("mirror_y", MirrorAxis::Y)]
+        .into_iter()
+        .filter_map(|(id, axis)| Some((last_index(&matches, id)?, axis)))
+        .max_by_key(|(i, _)| *i)
+        .map(|(_, axis)| axis);
+    let fractal = [
+        ("mandelbrot", Some(FractalKind::Mandelbrot)),
+        ("julia", cli.julia.map(|c| FractalKind::Julia { c })),
+    ]
+    .into_iter()
+    .filter_map(|(id, kind)| Some((last_index(&matches, id)?, kind?)))
+    .max_by_key(|(i, _)| *i)
+    .map(|(_, kind)| kind);
+
+    Parsed {
+        filters: filters.into_iter().map(|(_, filter)| filter).collect(),
+        paths: paths.into_iter().map(|(_, path)| path).collect(),
+        crop,
+        mirror,
+        fractal,
+        drop_shadow,
+        args: cli,
+    }
+}
+
+/* Every time the flag `id` was given with the position it was given at and its `per` values */
+fn occurrences<'a, T>(
+    matches: &ArgMatches,
+    id: &str,
+    values: &'a [T],
+    per: usize,
+) -> impl Iterator<Item = (usize, &'a [T])> {
+    given_indices(matches, id)
+        .into_iter()
+        .step_by(per)
+        .zip(values.chunks(per))
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/* Flags that weren't given still have an index for their default value, so skip those */
+fn given_indices(matches: &ArgMatches, id: &str) -> Vec<usize> {
+    if matches.value_source(id) != Some(ValueSource::CommandLine) {
+        return Vec::new();
+    }
+    matches.indices_of(id).into_iter().flatten().collect()
+}
+
+fn last_index(matches: &ArgMatches, id: &str) -> Option<usize> {
+    given_indices(matches, id).last().copied()
+}
+
+/* For mistakes that only show once all values are known, reported the way clap reports its own */
+fn invalid(flag: &str, msg: &str) -> ! {
+    CliArgs::command()
+        .error(
+            ErrorKind::ValueValidation,
+            format!("invalid value for '{}': {}", flag, msg),
+        )
+        .exit()
+}
+
+/* --inode is looked up right away, so the file is opened like any other path */
+#[cfg(target_os = "linux")]
+fn inode(spec: &str) -> Result<String, String> {
+    crate::inode::find_by_inode(spec)
+        .map(|path| path.to_string_lossy().into_owned())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn inode(_spec: &str) -> Result<String, String> {
+    Err("only supported on Linux".to_string())
+}
+
+/* Longer than anyone would wait for the next image, and far from overflowing an Instant */
+const MAX_SLIDESHOW_DELAY: f32 = 24.0 * 60.0 * 60.0;
+
+/// Parses the seconds between slides for --slideshow and :slideshow
+pub fn slideshow_delay(arg: &str) -> Result<Duration, String> {
+    let seconds = number_in((Excluded(0.0f32), Included(MAX_SLIDESHOW_DELAY)))(arg)?;
+    Duration::try_from_secs_f32(seconds).map_err(|e| e.to_string())
+}
+
+/* The flag's value is the argument of the filter with the same name */
+fn filter(name: &'static str) -> impl Fn(&str) -> Result<Filter, String> + Clone + Send + Sync {
+    move |arg| Filter::parse(&[name, arg])
+}
+
+fn hsl_adjust(arg: &str) -> Result<Filter, String> {
+    let parts: Vec<&str> = arg.split(':').collect();
+    Filter::parse(&[&["hsl"], parts.as_slice()].concat())
+}
+
+/* The filter and its arguments are one value, e.g. --split-view "cartoon 8" */
+fn split_view(arg: &str) -> Result<Filter, String> {
+    Filter::parse(&arg.split_whitespace().collect::<Vec<_>>())
+}
+
+/* Any number but NaN and the infinities */
+fn finite(arg: &str) -> Result<f32, String> {
+    match arg.parse::<f32>() {
+        Ok(n) if n.is_finite() => Ok(n),
+        Ok(_) => Err(format!("{} is not a finite number", arg)),
+        Err(_) => Err(format!("{} is not a number", arg)),
+    }
+}
+
+/* A number that has to lie in `range`, e.g. a probability */
+fn number_in<T, R>(range: R) -> impl Fn(&str) -> Result<T, String> + Clone + Send + Sync + 'static
+where
+    T: FromStr + PartialOrd + Display,
+    R: RangeBounds<T> + Clone + Send + Sync + 'static,
+{
+    move |arg| match arg.parse::<T>() {
+        Ok(n) if range.contains(&n) => Ok(n),
+        Ok(_) => Err(format!("must be {}", describe(&range))),
+        /* Too big for the type, or negative for an unsigned one */
+        Err(_) if arg.parse::<f64>().is_ok() => Err(format!("{} is out of range", arg)),
+        Err(_) => Err(format!("{} is not a number", arg)),
+    }
+}
+
+fn describe<T: Display>(range: &impl RangeBounds<T>) -> String {
+    match (range.start_bound(), range.end_bound()) {
+        (Included(low), Unbounded) => format!("at least {}", low),
+        (Excluded(low), Unbounded) => format!("greater than {}", low),
+        (Included(low), Included(high)) => format!("between {} and {}", low, high),
+        (Excluded(low), Included(high)) => format!("greater than {} and at most {}", low, high),
+        _ => "in range".to_string(),
+    }
+}
+
+/// Prints the completion script for `shell` to stdout
+pub fn print_completions(shell: Shell) {
+    let mut command = CliArgs::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+}
+
+/// Prints the help, for when there is nothing to open
+pub fn print_help() {
+    let _ = CliArgs::command().print_help();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slideshow_delay_has_to_fit_a_duration() {
+        assert_eq!(slideshow_delay("0.5"), Ok(Duration::from_millis(500)));
+        for arg in ["0", "-1", "inf", "NaN", "1e30"] {
+            assert!(slideshow_delay(arg).is_err(), "{} was accepted", arg);
+        }
+    }
+
+    #[test]
+    fn split_tone_hues_have_to_be_finite() {
+        let parse = |hues: &[&str]| {
+            CliArgs::try_parse_from([&["charcoal", "--split-tone"], hues, &["a.png"]].concat())
+        };
+        assert_eq!(parse(&["-20", "200"]).unwrap().split_tone, [-20.0, 200.0]);
+        assert!(parse(&["NaN", "200"]).is_err());
+        assert!(parse(&["20", "inf"]).is_err());
+    }
+}
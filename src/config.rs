@@ -0,0 +1,96 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use charcoal_core::{
+    Renderer,
+    ascii::{AsciiOptions, DEFAULT_CHAR_ASPECT, DEFAULT_CHARSET, DEFAULT_MAX_WIDTH},
+};
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+/* Settings that can come from the config file or the command line. Everything is optional so
+ * the two can be layered: CLI flags first, then the config file, then the hard-coded defaults */
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    pub max_width: Option<u32>,
+    pub char_aspect: Option<f32>,
+    pub charset: Option<String>,
+    pub renderer: Option<Renderer>,
+    /* Maps a key in Normal mode to a command, e.g. `c = "filter cartoon 8"` */
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub keybindings: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// `~/.config/charcoal/config.toml`, or wherever the platform keeps its config files
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("charcoal").join("config.toml"))
+    }
+
+    /// Loads the config from `path`, or from the default location if none is given. A missing
+    /// default config is not an error, a missing explicit one is
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+        let text = fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Failed to read config {}", path.display()))?;
+        toml::from_str(&text).wrap_err_with(|| format!("Invalid config {}", path.display()))
+    }
+
+    /// Fills every setting missing in `self` from `fallback`
+    pub fn or(self, fallback: Config) -> Config {
+        let mut keybindings = fallback.keybindings;
+        keybindings.extend(self.keybindings);
+        Config {
+            max_width: self.max_width.or(fallback.max_width),
+            char_aspect: self.char_aspect.or(fallback.char_aspect),
+            charset: self.charset.or(fallback.charset),
+            renderer: self.renderer.or(fallback.renderer),
+            keybindings,
+        }
+    }
+
+    /// Fills the remaining gaps with the hard-coded defaults
+    pub fn with_defaults(self, renderer: Renderer) -> Config {
+        Config {
+            max_width: self.max_width.or(Some(DEFAULT_MAX_WIDTH)),
+            char_aspect: self.char_aspect.or(Some(DEFAULT_CHAR_ASPECT)),
+            charset: self.charset.or(Some(DEFAULT_CHARSET.to_string())),
+            renderer: self.renderer.or(Some(renderer)),
+            keybindings: self.keybindings,
+        }
+    }
+
+    pub fn ascii_options(&self) -> AsciiOptions {
+        let defaults = AsciiOptions::default();
+        AsciiOptions {
+            max_width: self.max_width.unwrap_or(defaults.max_width),
+            char_aspect: self.char_aspect.unwrap_or(defaults.char_aspect),
+            charset: self.charset.clone().unwrap_or(defaults.charset),
+            ..defaults
+        }
+    }
+
+    /// Keybindings on single characters, longer names can't be typed in Normal mode
+    pub fn key_commands(&self) -> BTreeMap<char, String> {
+        self.keybindings
+            .iter()
+            .filter_map(|(key, cmd)| {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some((c, cmd.clone())),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,154 @@
+/* Reading the EXIF metadata of photos */
+use std::{fs::File, io::BufReader, path::Path};
+
+use ::exif::{DateTime, Exif, Field, In, Reader, Tag, Value};
+use color_eyre::eyre::Result;
+use image::DynamicImage;
+use serde_json::{Map, Value as Json, json};
+
+/// Parses the EXIF data of the image at `path`
+pub fn read(path: &Path) -> Result<Exif> {
+    let mut reader = BufReader::new(File::open(path)?);
+    Ok(Reader::new().read_from_container(&mut reader)?)
+}
+
+/* Degrees, minutes and seconds to decimal degrees, negative in the south and west */
+fn coordinate(exif: &Exif, tag: Tag, reference: Tag, negative: &str) -> Option<f64> {
+    let Value::Rational(dms) = &exif.get_field(tag, In::PRIMARY)?.value else {
+        return None;
+    };
+    let [degrees, minutes, seconds] = dms.as_slice() else {
+        return None;
+    };
+    let value = degrees.to_f64() + minutes.to_f64() / 60.0 + seconds.to_f64() / 3600.0;
+    let hemisphere = exif
+        .get_field(reference, In::PRIMARY)?
+        .display_value()
+        .to_string();
+    Some(if hemisphere.contains(negative) {
+        -value
+    } else {
+        value
+    })
+}
+
+/// Latitude and longitude where the photo was taken, in decimal degrees
+pub fn gps(exif: &Exif) -> Option<(f64, f64)> {
+    Some((
+        coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, "S")?,
+        coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, "W")?,
+    ))
+}
+
+/// When the photo was taken according to DateTimeOriginal, as `YYYY-MM-DD_HH-MM-SS`
+pub fn date_taken(exif: &Exif) -> Option<String> {
+    let Value::Ascii(strings) = &exif.get_field(Tag::DateTimeOriginal, In::PRIMARY)?.value else {
+        return None;
+    };
+    let date = DateTime::from_ascii(strings.first()?).ok()?;
+    Some(format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        date.year, date.month, date.day, date.hour, date.minute, date.second
+    ))
+}
+
+/// The orientation tag, 1 (normal) to 8, telling how the camera was held
+pub fn orientation(exif: &Exif) -> Option<u32> {
+    exif.get_field(Tag::Orientation, In::PRIMARY)?
+        .value
+        .get_uint(0)
+        .filter(|orientation| (1..=8).contains(orientation))
+}
+
+/// Turns `img` the way `orientation` says, so it looks right without the tag
+pub fn apply_orientation(img: &DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img.clone(),
+    }
+}
+
+/// A position like `37.7749°N 122.4194°W`
+pub fn format_gps((lat, lon): (f64, f64)) -> String {
+    format!(
+        "{:.4}°{} {:.4}°{}",
+        lat.abs(),
+        if lat < 0.0 { 'S' } else { 'N' },
+        lon.abs(),
+        if lon < 0.0 { 'W' } else { 'E' }
+    )
+}
+
+/* A single number stays a number, several become an array */
+fn numbers<T: Into<Json> + Copy>(values: &[T]) -> Json {
+    match values {
+        [value] => (*value).into(),
+        _ => values.iter().map(|&v| v.into()).collect(),
+    }
+}
+
+fn to_json(field: &Field) -> Json {
+    match &field.value {
+        Value::Ascii(strings) => {
+            let mut strings = strings
+                .iter()
+                .map(|s| String::from_utf8_lossy(s).into_owned());
+            match strings.len() {
+                1 => strings.next().unwrap().into(),
+                _ => strings.collect(),
+            }
+        }
+        Value::Byte(v) => numbers(v),
+        Value::Short(v) => numbers(v),
+        Value::Long(v) => numbers(v),
+        Value::SByte(v) => numbers(v),
+        Value::SShort(v) => numbers(v),
+        Value::SLong(v) => numbers(v),
+        Value::Float(v) => numbers(v),
+        Value::Double(v) => numbers(v),
+        Value::Rational(v) => numbers(&v.iter().map(|r| r.to_f64()).collect::<Vec<_>>()),
+        Value::SRational(v) => numbers(&v.iter().map(|r| r.to_f64()).collect::<Vec<_>>()),
+        /* Versions, maker notes and the like, shown the way the standard describes them */
+        Value::Undefined(..) | Value::Unknown(..) => field.display_value().to_string().into(),
+    }
+}
+
+/* The value of a tag that isn't in the standard, in hex if it is a plain number */
+fn unknown_to_json(field: &Field) -> Json {
+    match (field.value.get_uint(0), field.value.get_uint(1)) {
+        (Some(value), None) => format!("0x{:x}", value).into(),
+        _ => field.display_value().to_string().into(),
+    }
+}
+
+/// Every field as a JSON object keyed by tag name, e.g. `{"Make": "Canon", ...}`. Tags this
+/// doesn't know are written as `"Tag(0x1234)"`, fields of the thumbnail start with
+/// `Thumbnail.`. The position is added as `GPSLat` and `GPSLon` in decimal degrees
+pub fn to_json_object(exif: &Exif) -> Json {
+    let mut object = Map::new();
+    for field in exif.fields() {
+        let (name, value) = match field.tag.description() {
+            Some(_) => (field.tag.to_string(), to_json(field)),
+            None => (
+                format!("Tag(0x{:04x})", field.tag.number()),
+                unknown_to_json(field),
+            ),
+        };
+        let name = match field.ifd_num {
+            In::PRIMARY => name,
+            _ => format!("Thumbnail.{}", name),
+        };
+        object.insert(name, value);
+    }
+    if let Some((lat, lon)) = gps(exif) {
+        object.insert("GPSLat".to_string(), json!(lat));
+        object.insert("GPSLon".to_string(), json!(lon));
+    }
+    Json::Object(object)
+}
@@ -0,0 +1,42 @@
+use image::{DynamicImage, GenericImageView, Rgba};
+
+/* Median of every channel of the outermost pixels, so a few specks on the border don't change
+ * the color that gets cropped */
+fn border_color(img: &DynamicImage) -> Rgba<u8> {
+    let (w, h) = img.dimensions();
+    let mut border: Vec<Rgba<u8>> = (0..w)
+        .flat_map(|x| [img.get_pixel(x, 0), img.get_pixel(x, h - 1)])
+        .chain((0..h).flat_map(|y| [img.get_pixel(0, y), img.get_pixel(w - 1, y)]))
+        .collect();
+    let mid = border.len() / 2;
+    let mut color = [0; 4];
+    for (c, value) in color.iter_mut().enumerate() {
+        border.sort_unstable_by_key(|p| p[c]);
+        *value = border[mid][c];
+    }
+    Rgba(color)
+}
+
+/// Crops the borders of the image's border color, found by sampling the outermost pixels.
+/// Every side is scanned inward until a pixel differs from that color by more than `threshold`
+/// in any channel. An image of a single color is returned as it is
+pub fn auto_crop(img: &DynamicImage, threshold: u8) -> DynamicImage {
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 {
+        return img.clone();
+    }
+    let background = border_color(img);
+    let differs = |x: u32, y: u32| {
+        let p = img.get_pixel(x, y);
+        (0..4).any(|c| p[c].abs_diff(background[c]) > threshold)
+    };
+    let row_differs = |y: u32| (0..w).any(|x| differs(x, y));
+    let Some(top) = (0..h).find(|&y| row_differs(y)) else {
+        return img.clone();
+    };
+    let bottom = (top..h).rev().find(|&y| row_differs(y)).unwrap_or(top);
+    let column_differs = |x: u32| (top..=bottom).any(|y| differs(x, y));
+    let left = (0..w).find(|&x| column_differs(x)).unwrap_or(0);
+    let right = (left..w).rev().find(|&x| column_differs(x)).unwrap_or(left);
+    img.crop_imm(left, top, right - left + 1, bottom - top + 1)
+}
@@ -0,0 +1,57 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+/* Weights of a gaussian reaching `radius` pixels to either side, summing to 1. The standard
+ * deviation is half the radius so the kernel isn't cut off where it still matters */
+fn kernel(radius: u32) -> Vec<f32> {
+    let sigma = (radius as f32 / 2.0).max(0.5);
+    let weights: Vec<f32> = (-(radius as i32)..=radius as i32)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f32 = weights.iter().sum();
+    weights.iter().map(|w| w / total).collect()
+}
+
+/* One pass of the separable blur, along rows if `horizontal` and along columns otherwise. Edge
+ * pixels are repeated */
+fn convolve(src: &RgbaImage, kernel: &[f32], horizontal: bool) -> RgbaImage {
+    let (width, height) = src.dimensions();
+    let radius = (kernel.len() / 2) as i64;
+    RgbaImage::from_fn(width, height, |x, y| {
+        let mut sum = [0.0f32; 4];
+        for (i, weight) in kernel.iter().enumerate() {
+            let offset = i as i64 - radius;
+            let p = if horizontal {
+                src.get_pixel((x as i64 + offset).clamp(0, width as i64 - 1) as u32, y)
+            } else {
+                src.get_pixel(x, (y as i64 + offset).clamp(0, height as i64 - 1) as u32)
+            };
+            for c in 0..4 {
+                sum[c] += p[c] as f32 * weight;
+            }
+        }
+        Rgba(sum.map(|s| s.round().clamp(0.0, 255.0) as u8))
+    })
+}
+
+/// Gaussian blur reaching `radius` pixels, done as a horizontal and a vertical pass
+pub fn gaussian_blur(img: &DynamicImage, radius: u32) -> DynamicImage {
+    let kernel = kernel(radius);
+    let rows = convolve(&img.to_rgba8(), &kernel, true);
+    DynamicImage::ImageRgba8(convolve(&rows, &kernel, false))
+}
+
+/// Sharpens by adding the difference to a blurred copy, scaled by `amount`. Differences of at
+/// most `threshold` are left alone so flat areas don't get noisy
+pub fn unsharp_mask(img: &DynamicImage, radius: u32, amount: f32, threshold: u8) -> DynamicImage {
+    let mut out = img.to_rgba8();
+    let blurred = gaussian_blur(img, radius).to_rgba8();
+    for (p, b) in out.pixels_mut().zip(blurred.pixels()) {
+        for c in 0..3 {
+            let diff = p[c] as f32 - b[c] as f32;
+            if diff.abs() > threshold as f32 {
+                p[c] = (p[c] as f32 + diff * amount).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+    DynamicImage::ImageRgba8(out)
+}
@@ -0,0 +1,104 @@
+use image::{DynamicImage, Rgb, RgbImage};
+
+use super::edge::canny;
+
+/// Cartoon effect: smooth colors, reduce them to `k` clusters and draw the edges in black
+pub fn cartoon(img: &DynamicImage, k: u32) -> DynamicImage {
+    let smooth = bilateral(&img.to_rgb8(), 2, 3.0, 30.0);
+    let edges = canny(
+        &DynamicImage::ImageRgb8(smooth.clone()).to_luma8(),
+        40.0,
+        100.0,
+    );
+
+    let mut out = quantize(&smooth, k, 10);
+    for (x, y, pixel) in out.enumerate_pixels_mut() {
+        if edges.get_pixel(x, y)[0] > 0 {
+            *pixel = Rgb([0, 0, 0]);
+        }
+    }
+    DynamicImage::ImageRgb8(out)
+}
+
+/* Edge preserving blur: neighbours are weighted by distance and by how similar their color is */
+fn bilateral(img: &RgbImage, radius: i32, sigma_space: f32, sigma_color: f32) -> RgbImage {
+    let (w, h) = img.dimensions();
+    let space_weight = |dx: i32, dy: i32| {
+        (-((dx * dx + dy * dy) as f32) / (2.0 * sigma_space * sigma_space)).exp()
+    };
+
+    RgbImage::from_fn(w, h, |x, y| {
+        let center = img.get_pixel(x, y);
+        let mut sum = [0.0f32; 3];
+        let mut total = 0.0;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let nx = (x as i32 + dx).clamp(0, w as i32 - 1) as u32;
+                let ny = (y as i32 + dy).clamp(0, h as i32 - 1) as u32;
+                let p = img.get_pixel(nx, ny);
+                let dist2: f32 = (0..3)
+                    .map(|c| (p[c] as f32 - center[c] as f32).powi(2))
+                    .sum();
+                let weight =
+                    space_weight(dx, dy) * (-dist2 / (2.0 * sigma_color * sigma_color)).exp();
+                for c in 0..3 {
+                    sum[c] += p[c] as f32 * weight;
+                }
+                total += weight;
+            }
+        }
+        Rgb(sum.map(|s| (s / total).round() as u8))
+    })
+}
+
+fn distance2(a: [f32; 3], b: &Rgb<u8>) -> f32 {
+    (0..3).map(|c| (a[c] - b[c] as f32).powi(2)).sum()
+}
+
+fn nearest(centroids: &[[f32; 3]], p: &Rgb<u8>) -> usize {
+    (0..centroids.len())
+        .min_by(|&a, &b| distance2(centroids[a], p).total_cmp(&distance2(centroids[b], p)))
+        .unwrap_or(0)
+}
+
+/* k-means color quantization, trained on a subsample of the pixels to keep it fast */
+fn quantize(img: &RgbImage, k: u32, iterations: usize) -> RgbImage {
+    let pixels: Vec<&Rgb<u8>> = img.pixels().collect();
+    let step = (pixels.len() / 10_000).max(1);
+    let samples: Vec<&Rgb<u8>> = pixels.iter().step_by(step).copied().collect();
+    if samples.is_empty() {
+        return img.clone();
+    }
+
+    let k = (k as usize).min(samples.len());
+    let mut centroids: Vec<[f32; 3]> = (0..k)
+        .map(|i| samples[i * samples.len() / k].0.map(|c| c as f32))
+        .collect();
+
+    for _ in 0..iterations {
+        let mut sums = vec![[0.0f32; 3]; k];
+        let mut counts = vec![0u32; k];
+        for p in &samples {
+            let i = nearest(&centroids, p);
+            for c in 0..3 {
+                sums[i][c] += p[c] as f32;
+            }
+            counts[i] += 1;
+        }
+        for i in 0..k {
+            if counts[i] > 0 {
+                centroids[i] = sums[i].map(|s| s / counts[i] as f32);
+            }
+        }
+    }
+
+    let palette: Vec<Rgb<u8>> = centroids
+        .iter()
+        .map(|c| Rgb(c.map(|v| v.round() as u8)))
+        .collect();
+    let mut out = img.clone();
+    for p in out.pixels_mut() {
+        *p = palette[nearest(&centroids, p)];
+    }
+    out
+}
@@ -0,0 +1,101 @@
+use image::{DynamicImage, Rgba};
+
+use super::color::{rgb_to_hsv, rgb_to_ycbcr};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channel {
+    R,
+    G,
+    B,
+    A,
+    H,
+    S,
+    V,
+    Y,
+    Cb,
+    Cr,
+}
+
+/* The channels `c` steps through, the YCbCr ones are only a command away */
+const CYCLE: [Channel; 7] = [
+    Channel::R,
+    Channel::G,
+    Channel::B,
+    Channel::A,
+    Channel::H,
+    Channel::S,
+    Channel::V,
+];
+
+impl Channel {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "r" => Ok(Channel::R),
+            "g" => Ok(Channel::G),
+            "b" => Ok(Channel::B),
+            "a" => Ok(Channel::A),
+            "h" => Ok(Channel::H),
+            "s" => Ok(Channel::S),
+            "v" => Ok(Channel::V),
+            "y" => Ok(Channel::Y),
+            "cb" => Ok(Channel::Cb),
+            "cr" => Ok(Channel::Cr),
+            _ => Err(format!("Unknown channel: {}", s)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Channel::R => "R",
+            Channel::G => "G",
+            Channel::B => "B",
+            Channel::A => "A",
+            Channel::H => "H",
+            Channel::S => "S",
+            Channel::V => "V",
+            Channel::Y => "Y",
+            Channel::Cb => "Cb",
+            Channel::Cr => "Cr",
+        }
+    }
+
+    /// The channel after `channel` in R, G, B, A, H, S, V, None after the last one
+    pub fn cycle(channel: Option<Channel>) -> Option<Channel> {
+        match channel {
+            None => Some(CYCLE[0]),
+            Some(channel) => CYCLE
+                .iter()
+                .position(|c| *c == channel)
+                .and_then(|i| CYCLE.get(i + 1))
+                .copied(),
+        }
+    }
+}
+
+/// Shows a single channel of the image. R, G and B keep their own tint, the others are shown as
+/// grayscale
+pub fn isolate_channel(img: &DynamicImage, channel: Channel) -> DynamicImage {
+    let mut out = img.to_rgba8();
+    for p in out.pixels_mut() {
+        let [r, g, b, a] = p.0;
+        let (y, cb, cr) = rgb_to_ycbcr(r, g, b);
+        let (h, s, v) = rgb_to_hsv(r, g, b);
+        /* Chroma barely moves away from 128, so stretch it to make it visible */
+        let stretch = |c: f32| (128.0 + (c - 128.0) * 2.0).round().clamp(0.0, 255.0) as u8;
+        let gray = |v: u8| Rgba([v, v, v, a]);
+        *p = match channel {
+            Channel::R => Rgba([r, 0, 0, a]),
+            Channel::G => Rgba([0, g, 0, a]),
+            Channel::B => Rgba([0, 0, b, a]),
+            /* Opaque, or transparent pixels would hide their own alpha */
+            Channel::A => Rgba([a, a, a, 255]),
+            Channel::H => gray((h / 360.0 * 255.0).round() as u8),
+            Channel::S => gray((s * 255.0).round() as u8),
+            Channel::V => gray((v * 255.0).round() as u8),
+            Channel::Y => gray(y.round() as u8),
+            Channel::Cb => gray(stretch(cb)),
+            Channel::Cr => gray(stretch(cr)),
+        };
+    }
+    DynamicImage::ImageRgba8(out)
+}
@@ -0,0 +1,19 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+/// Shifts the red channel `px` pixels to the left and the blue channel `px` pixels to the right,
+/// green stays where it is. Edge pixels are repeated to fill the gaps
+pub fn chromatic_aberration(img: &DynamicImage, px: u32) -> DynamicImage {
+    let src = img.to_rgba8();
+    let (width, height) = src.dimensions();
+    let px = px as i64;
+    let at = |x: i64, y: u32| *src.get_pixel(x.clamp(0, width as i64 - 1) as u32, y);
+
+    let mut out = RgbaImage::new(width, height);
+    for (x, y, p) in out.enumerate_pixels_mut() {
+        let x = x as i64;
+        let Rgba([_, g, _, a]) = at(x, y);
+        /* Shifting red left means each pixel takes it from its right neighbour */
+        *p = Rgba([at(x + px, y)[0], g, at(x - px, y)[2], a]);
+    }
+    DynamicImage::ImageRgba8(out)
+}
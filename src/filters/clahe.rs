@@ -0,0 +1,91 @@
+use image::DynamicImage;
+
+use super::color::{rgb_to_ycbcr, ycbcr_to_rgb};
+
+/* Equalization table for one tile, with the histogram clipped at `limit` and the excess spread
+ * out evenly over all bins */
+fn clipped_table(hist: &mut [u32; 256], limit: u32) -> [u8; 256] {
+    let mut excess = 0;
+    for count in hist.iter_mut() {
+        if *count > limit {
+            excess += *count - limit;
+            *count = limit;
+        }
+    }
+    let (share, rest) = (excess / 256, excess % 256);
+    for (i, count) in hist.iter_mut().enumerate() {
+        *count += share + u32::from((i as u32) < rest);
+    }
+
+    let total: u64 = hist.iter().map(|&c| c as u64).sum();
+    let mut table = [0u8; 256];
+    let mut cdf = 0u64;
+    for (entry, &count) in table.iter_mut().zip(hist.iter()) {
+        cdf += count as u64;
+        *entry = (cdf as f64 * 255.0 / total.max(1) as f64).round() as u8;
+    }
+    table
+}
+
+/* Index of the two tiles around `pos` and how far `pos` is from the first one */
+fn neighbours(pos: u32, tile_size: u32, tiles: u32) -> (usize, usize, f32) {
+    let g = (pos as f32 + 0.5) / tile_size as f32 - 0.5;
+    if g <= 0.0 {
+        return (0, 0, 0.0);
+    }
+    let t0 = (g.floor() as u32).min(tiles - 1);
+    let t1 = (t0 + 1).min(tiles - 1);
+    (t0 as usize, t1 as usize, (g - t0 as f32).clamp(0.0, 1.0))
+}
+
+/// Contrast Limited Adaptive Histogram Equalization on the luma of the image. `clip_limit` is a
+/// multiple of the average bin height, small values (2-4) give a natural look
+pub fn clahe(img: &DynamicImage, clip_limit: f32, tile_size: u32) -> DynamicImage {
+    let mut rgba = img.to_rgba8();
+    let (w, h) = rgba.dimensions();
+    let tile_size = tile_size.max(1);
+    let (tiles_x, tiles_y) = (w.div_ceil(tile_size).max(1), h.div_ceil(tile_size).max(1));
+    let luma = |x: u32, y: u32| {
+        let p = rgba.get_pixel(x, y);
+        rgb_to_ycbcr(p[0], p[1], p[2]).0.round() as usize
+    };
+
+    /* 1) One clipped equalization table per tile */
+    let mut tables = Vec::with_capacity((tiles_x * tiles_y) as usize);
+    for ty in 0..tiles_y {
+        for tx in 0..tiles_x {
+            let mut hist = [0u32; 256];
+            let (x0, y0) = (tx * tile_size, ty * tile_size);
+            let (x1, y1) = ((x0 + tile_size).min(w), (y0 + tile_size).min(h));
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    hist[luma(x, y)] += 1;
+                }
+            }
+            let pixels = (x1 - x0) * (y1 - y0);
+            let limit = ((clip_limit * pixels as f32 / 256.0).ceil() as u32).max(1);
+            tables.push(clipped_table(&mut hist, limit));
+        }
+    }
+
+    /* 2) Map every pixel with the bilinear blend of the four surrounding tiles */
+    let table = |tx: usize, ty: usize| &tables[ty * tiles_x as usize + tx];
+    let mut mapped = vec![0.0f32; (w * h) as usize];
+    for y in 0..h {
+        let (ty0, ty1, fy) = neighbours(y, tile_size, tiles_y);
+        for x in 0..w {
+            let (tx0, tx1, fx) = neighbours(x, tile_size, tiles_x);
+            let v = luma(x, y);
+            let top = table(tx0, ty0)[v] as f32 * (1.0 - fx) + table(tx1, ty0)[v] as f32 * fx;
+            let bottom = table(tx0, ty1)[v] as f32 * (1.0 - fx) + table(tx1, ty1)[v] as f32 * fx;
+            mapped[(y * w + x) as usize] = top * (1.0 - fy) + bottom * fy;
+        }
+    }
+
+    for (x, y, p) in rgba.enumerate_pixels_mut() {
+        let (_, cb, cr) = rgb_to_ycbcr(p[0], p[1], p[2]);
+        let (r, g, b) = ycbcr_to_rgb(mapped[(y * w + x) as usize], cb, cr);
+        p.0 = [r, g, b, p[3]];
+    }
+    DynamicImage::ImageRgba8(rgba)
+}
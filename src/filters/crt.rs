@@ -0,0 +1,71 @@
+/* The steps of the CRT look, each one is a filter of its own so they can be combined freely */
+use charcoal_core::sample_bilinear;
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+
+/// Bulges the image outwards like the curved glass of a CRT. `strength` is how much further out
+/// the corners are sampled from, parts that end up outside the image are black
+pub fn barrel_distortion(img: &DynamicImage, strength: f32) -> DynamicImage {
+    let (width, height) = img.dimensions();
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+    let mut out = RgbaImage::new(width, height);
+    /* Inverse mapping: for every output pixel find where it comes from */
+    for (x, y, p) in out.enumerate_pixels_mut() {
+        let nx = (x as f32 + 0.5 - cx) / cx;
+        let ny = (y as f32 + 0.5 - cy) / cy;
+        let scale = 1.0 + strength * (nx * nx + ny * ny) / 2.0;
+        let (sx, sy) = (nx * scale, ny * scale);
+        if sx.abs() <= 1.0 && sy.abs() <= 1.0 {
+            *p = sample_bilinear(img, sx * cx + cx - 0.5, sy * cy + cy - 0.5);
+        } else {
+            *p = Rgba([0, 0, 0, 255]);
+        }
+    }
+    DynamicImage::ImageRgba8(out)
+}
+
+/// Darkens every other row by `darken` (0.0..=1.0)
+pub fn scanlines(img: &DynamicImage, darken: f32) -> DynamicImage {
+    let mut out = img.to_rgba8();
+    for (_, y, p) in out.enumerate_pixels_mut() {
+        if y % 2 == 1 {
+            let Rgba([r, g, b, a]) = *p;
+            let dim = |c: u8| (c as f32 * (1.0 - darken)).round() as u8;
+            *p = Rgba([dim(r), dim(g), dim(b), a]);
+        }
+    }
+    DynamicImage::ImageRgba8(out)
+}
+
+/* How much of the other two channels a phosphor stripe lets through */
+const STRIPE_LEAK: f32 = 0.6;
+
+/// Splits every column into red, green and blue stripes like the phosphors of an aperture
+/// grille, each stripe dims the channels that aren't its own
+pub fn phosphor_mask(img: &DynamicImage) -> DynamicImage {
+    let mut out = img.to_rgba8();
+    for (x, _, p) in out.enumerate_pixels_mut() {
+        let stripe = (x % 3) as usize;
+        for c in 0..3 {
+            if c != stripe {
+                p[c] = (p[c] as f32 * STRIPE_LEAK).round() as u8;
+            }
+        }
+    }
+    DynamicImage::ImageRgba8(out)
+}
+
+/* Share of the blurred copy added back on top */
+const GLOW_AMOUNT: f32 = 0.35;
+
+/// Makes bright areas bleed into their surroundings by adding a gaussian blurred copy with a
+/// standard deviation of `sigma` pixels
+pub fn glow(img: &DynamicImage, sigma: f32) -> DynamicImage {
+    let blurred = img.blur(sigma).to_rgba8();
+    let mut out = img.to_rgba8();
+    for (p, b) in out.pixels_mut().zip(blurred.pixels()) {
+        for c in 0..3 {
+            p[c] = (p[c] as f32 + b[c] as f32 * GLOW_AMOUNT).min(255.0) as u8;
+        }
+    }
+    DynamicImage::ImageRgba8(out)
+}
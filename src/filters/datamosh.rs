@@ -0,0 +1,16 @@
+use std::io::Cursor;
+
+use image::{DynamicImage, codecs::jpeg::JpegEncoder};
+
+/// Re-encodes the image as a very low quality JPEG and decodes it again, keeping the artifacts
+pub fn datamosh(img: &DynamicImage, quality: u8) -> DynamicImage {
+    let mut bytes = Vec::new();
+    let rgb = DynamicImage::ImageRgb8(img.to_rgb8());
+    if rgb
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, quality))
+        .is_err()
+    {
+        return img.clone();
+    }
+    image::load(Cursor::new(bytes), image::ImageFormat::Jpeg).unwrap_or_else(|_| img.clone())
+}
@@ -0,0 +1,148 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+/* Sorting the whole window gets slow beyond this, larger radii keep a running histogram */
+const SORT_MAX_RADIUS: u32 = 3;
+
+/// Replaces every pixel's R, G and B with the median of its `(2 * radius + 1)²` neighbourhood,
+/// which removes salt-and-pepper noise while keeping edges sharp
+pub fn median_filter(img: &DynamicImage, radius: u32) -> DynamicImage {
+    if radius <= SORT_MAX_RADIUS {
+        sorted_median(img, radius)
+    } else {
+        histogram_median(img, radius)
+    }
+}
+
+fn sorted_median(img: &DynamicImage, radius: u32) -> DynamicImage {
+    let src = img.to_rgba8();
+    let (w, h) = src.dimensions();
+    let r = radius as i64;
+    let mut window: [Vec<u8>; 3] = Default::default();
+
+    let out = RgbaImage::from_fn(w, h, |x, y| {
+        for values in window.iter_mut() {
+            values.clear();
+        }
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let nx = (x as i64 + dx).clamp(0, w as i64 - 1) as u32;
+                let ny = (y as i64 + dy).clamp(0, h as i64 - 1) as u32;
+                let p = src.get_pixel(nx, ny);
+                for (c, values) in window.iter_mut().enumerate() {
+                    values.push(p[c]);
+                }
+            }
+        }
+        let median = |values: &mut Vec<u8>| {
+            values.sort_unstable();
+            values[values.len() / 2]
+        };
+        Rgba([
+            median(&mut window[0]),
+            median(&mut window[1]),
+            median(&mut window[2]),
+            src.get_pixel(x, y)[3],
+        ])
+    });
+    DynamicImage::ImageRgba8(out)
+}
+
+/* Slides the window along each row, so only the column leaving it and the one entering it are
+ * counted for every pixel instead of the whole window */
+fn histogram_median(img: &DynamicImage, radius: u32) -> DynamicImage {
+    let src = img.to_rgba8();
+    let (w, h) = src.dimensions();
+    let r = radius as i64;
+    /* The median is the value below which half of the window lies, like values[len / 2] */
+    let half = ((2 * r + 1).pow(2) / 2) as u32;
+    let column = |x: i64| x.clamp(0, w as i64 - 1) as u32;
+    let mut out = RgbaImage::new(w, h);
+
+    for y in 0..h {
+        let mut histograms = [[0u32; 256]; 3];
+        for x in -r..=r {
+            count_column(&mut histograms, &src, column(x), y, r, true);
+        }
+        for x in 0..w {
+            if x > 0 {
+                count_column(&mut histograms, &src, column(x as i64 - 1 - r), y, r, false);
+                count_column(&mut histograms, &src, column(x as i64 + r), y, r, true);
+            }
+            let [red, green, blue] = histograms.each_ref().map(|counts| median(counts, half));
+            out.put_pixel(x, y, Rgba([red, green, blue, src.get_pixel(x, y)[3]]));
+        }
+    }
+    DynamicImage::ImageRgba8(out)
+}
+
+/* Adds or removes the pixels of column `x` within `radius` rows of `y` */
+fn count_column(
+    histograms: &mut [[u32; 256]; 3],
+    src: &RgbaImage,
+    x: u32,
+    y: u32,
+    radius: i64,
+    add: bool,
+) {
+    for dy in -radius..=radius {
+        let ny = (y as i64 + dy).clamp(0, src.height() as i64 - 1) as u32;
+        let p = src.get_pixel(x, ny);
+        for (counts, &v) in histograms.iter_mut().zip(&p.0[..3]) {
+            if add {
+                counts[v as usize] += 1;
+            } else {
+                counts[v as usize] -= 1;
+            }
+        }
+    }
+}
+
+fn median(counts: &[u32; 256], half: u32) -> u8 {
+    let mut seen = 0;
+    for (v, &n) in counts.iter().enumerate() {
+        seen += n;
+        if seen > half {
+            return v as u8;
+        }
+    }
+    u8::MAX
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* Flat gray with every seventh pixel turned black or white */
+    fn salt_and_pepper() -> DynamicImage {
+        let noisy = RgbaImage::from_fn(32, 32, |x, y| match (y * 32 + x) % 7 {
+            0 if (x + y) % 2 == 0 => Rgba([0, 0, 0, 255]),
+            0 => Rgba([255, 255, 255, 255]),
+            _ => Rgba([128, 128, 128, 255]),
+        });
+        DynamicImage::ImageRgba8(noisy)
+    }
+
+    fn assert_close_to_flat(img: &DynamicImage) {
+        let img = img.to_rgba8();
+        for c in 0..3 {
+            let mean = img.pixels().map(|p| p[c] as f64).sum::<f64>() / img.pixels().len() as f64;
+            assert!(
+                (mean - 128.0).abs() < 12.8,
+                "channel {} has mean {}",
+                c,
+                mean
+            );
+        }
+        assert!(img.pixels().all(|p| p[0] == 128), "some noise survived");
+    }
+
+    #[test]
+    fn removes_salt_and_pepper() {
+        assert_close_to_flat(&median_filter(&salt_and_pepper(), 1));
+    }
+
+    #[test]
+    fn histogram_median_removes_salt_and_pepper() {
+        assert_close_to_flat(&median_filter(&salt_and_pepper(), SORT_MAX_RADIUS + 1));
+    }
+}
@@ -0,0 +1,36 @@
+use image::{DynamicImage, GenericImageView, Rgb, Rgba, RgbaImage, imageops};
+
+use super::gaussian_blur;
+
+/// Puts the image on a blurred silhouette of itself in `color`, moved by `(x, y)` pixels. The
+/// result grows to fit the shadow, and is transparent where neither covers it
+pub fn drop_shadow(
+    img: &DynamicImage,
+    (x, y): (i32, i32),
+    blur: u32,
+    color: Rgb<u8>,
+) -> DynamicImage {
+    let (w, h) = img.dimensions();
+    let (w, h, blur) = (w as i64, h as i64, blur as i64);
+    let (x, y) = (x as i64, y as i64);
+    /* Extent of the image at (0, 0) and of the shadow with room for the blur to spread out */
+    let left = (x - blur).min(0);
+    let top = (y - blur).min(0);
+    let right = (x + w + blur).max(w);
+    let bottom = (y + h + blur).max(h);
+
+    let src = img.to_rgba8();
+    let Rgb([r, g, b]) = color;
+    let mut shadow = RgbaImage::from_pixel(
+        (right - left) as u32,
+        (bottom - top) as u32,
+        Rgba([r, g, b, 0]),
+    );
+    for (px, py, p) in src.enumerate_pixels() {
+        let (sx, sy) = ((px as i64 + x - left) as u32, (py as i64 + y - top) as u32);
+        shadow.put_pixel(sx, sy, Rgba([r, g, b, p[3]]));
+    }
+    let mut out = gaussian_blur(&DynamicImage::ImageRgba8(shadow), blur as u32).to_rgba8();
+    imageops::overlay(&mut out, &src, -left, -top);
+    DynamicImage::ImageRgba8(out)
+}
@@ -0,0 +1,141 @@
+use image::{DynamicImage, GrayImage, Luma, Rgba, imageops};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum EdgeAlgorithm {
+    /* Gradient magnitude, so strong edges are bright and weak ones dim */
+    #[default]
+    Sobel,
+    /* Thin edges of one pixel, weak ones are kept only next to ones above `high` */
+    Canny {
+        low: f32,
+        high: f32,
+    },
+}
+
+impl EdgeAlgorithm {
+    /// Parses `sobel`, `canny` or `canny <low> <high>`
+    pub fn parse(args: &[&str]) -> Result<Self, String> {
+        match args {
+            ["sobel"] => Ok(EdgeAlgorithm::Sobel),
+            ["canny"] => Ok(EdgeAlgorithm::Canny {
+                low: 40.0,
+                high: 100.0,
+            }),
+            ["canny", low, high] => match (low.parse::<f32>(), high.parse::<f32>()) {
+                (Ok(low), Ok(high)) if 0.0 <= low && low <= high => {
+                    Ok(EdgeAlgorithm::Canny { low, high })
+                }
+                _ => Err("Usage: canny <low> <high>, with low <= high".to_string()),
+            },
+            _ => Err("Usage: edge sobel|canny [<low> <high>]".to_string()),
+        }
+    }
+}
+
+/* Horizontal and vertical Sobel gradients of a grayscale image, edges are clamped */
+fn sobel_gradients(gray: &GrayImage) -> (Vec<f32>, Vec<f32>) {
+    let (w, h) = gray.dimensions();
+    let at = |x: i64, y: i64| {
+        let x = x.clamp(0, w as i64 - 1) as u32;
+        let y = y.clamp(0, h as i64 - 1) as u32;
+        gray.get_pixel(x, y)[0] as f32
+    };
+
+    let mut gx = vec![0.0; (w * h) as usize];
+    let mut gy = vec![0.0; (w * h) as usize];
+    for y in 0..h as i64 {
+        for x in 0..w as i64 {
+            let i = (y as u32 * w + x as u32) as usize;
+            gx[i] = (at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1))
+                - (at(x - 1, y - 1) + 2.0 * at(x - 1, y) + at(x - 1, y + 1));
+            gy[i] = (at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1))
+                - (at(x - 1, y - 1) + 2.0 * at(x, y - 1) + at(x + 1, y - 1));
+        }
+    }
+    (gx, gy)
+}
+
+/// Sobel edge detector, the gradient magnitude of every pixel clamped to 255
+pub fn sobel(gray: &GrayImage) -> GrayImage {
+    let (w, h) = gray.dimensions();
+    let (gx, gy) = sobel_gradients(gray);
+    let mag = gx
+        .iter()
+        .zip(&gy)
+        .map(|(x, y)| x.hypot(*y).min(255.0) as u8);
+    GrayImage::from_raw(w, h, mag.collect()).expect("one gradient per pixel")
+}
+
+/// White edges on black, or with `colored` the original colors shown only where the edges are
+pub fn detect_edges(img: &DynamicImage, algorithm: EdgeAlgorithm, colored: bool) -> DynamicImage {
+    let gray = img.to_luma8();
+    let edges = match algorithm {
+        EdgeAlgorithm::Sobel => sobel(&gray),
+        EdgeAlgorithm::Canny { low, high } => canny(&gray, low, high),
+    };
+    if !colored {
+        return DynamicImage::ImageLuma8(edges);
+    }
+    let mut out = img.to_rgba8();
+    for (p, edge) in out.pixels_mut().zip(edges.pixels()) {
+        let Rgba([r, g, b, a]) = *p;
+        let scale = |c: u8| (c as u16 * edge[0] as u16 / 255) as u8;
+        *p = Rgba([scale(r), scale(g), scale(b), a]);
+    }
+    DynamicImage::ImageRgba8(out)
+}
+
+/// Canny edge detector, returns a binary image where edges are 255 and everything else is 0
+pub fn canny(gray: &GrayImage, low: f32, high: f32) -> GrayImage {
+    let (w, h) = gray.dimensions();
+    let blurred = imageops::blur(gray, 1.4);
+    let (gx, gy) = sobel_gradients(&blurred);
+    let mag: Vec<f32> = gx.iter().zip(&gy).map(|(x, y)| x.hypot(*y)).collect();
+    let idx = |x: u32, y: u32| (y * w + x) as usize;
+
+    /* 1) Non-maximum suppression along the gradient direction */
+    let mut thin = vec![0.0; mag.len()];
+    for y in 1..h.saturating_sub(1) {
+        for x in 1..w.saturating_sub(1) {
+            let i = idx(x, y);
+            let angle = gy[i].atan2(gx[i]).to_degrees().rem_euclid(180.0);
+            let (dx, dy): (i32, i32) = if !(22.5..157.5).contains(&angle) {
+                (1, 0)
+            } else if angle < 67.5 {
+                (1, 1)
+            } else if angle < 112.5 {
+                (0, 1)
+            } else {
+                (-1, 1)
+            };
+            let a = mag[idx((x as i32 + dx) as u32, (y as i32 + dy) as u32)];
+            let b = mag[idx((x as i32 - dx) as u32, (y as i32 - dy) as u32)];
+            if mag[i] >= a && mag[i] >= b {
+                thin[i] = mag[i];
+            }
+        }
+    }
+
+    /* 2) Double threshold and hysteresis, weak edges survive only if connected to strong ones */
+    let mut out = GrayImage::new(w, h);
+    let mut stack: Vec<(u32, u32)> = Vec::new();
+    for y in 0..h {
+        for x in 0..w {
+            if thin[idx(x, y)] >= high {
+                out.put_pixel(x, y, Luma([255]));
+                stack.push((x, y));
+            }
+        }
+    }
+    while let Some((x, y)) = stack.pop() {
+        for ny in y.saturating_sub(1)..=(y + 1).min(h - 1) {
+            for nx in x.saturating_sub(1)..=(x + 1).min(w - 1) {
+                if out.get_pixel(nx, ny)[0] == 0 && thin[idx(nx, ny)] >= low {
+                    out.put_pixel(nx, ny, Luma([255]));
+                    stack.push((nx, ny));
+                }
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,85 @@
+use image::DynamicImage;
+
+use super::color::{rgb_to_ycbcr, ycbcr_to_rgb};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EqualizeMode {
+    /* Every RGB channel on its own, which shifts the hue */
+    All,
+    R,
+    G,
+    B,
+    /* Only the luma, which keeps the hue */
+    Y,
+}
+
+impl EqualizeMode {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "all" => Ok(EqualizeMode::All),
+            "r" => Ok(EqualizeMode::R),
+            "g" => Ok(EqualizeMode::G),
+            "b" => Ok(EqualizeMode::B),
+            "y" => Ok(EqualizeMode::Y),
+            _ => Err(format!("Unknown equalize mode: {}", s)),
+        }
+    }
+}
+
+/// Maps every value through the normalized cumulative distribution of `hist`
+pub fn equalization_table(hist: &[u32; 256]) -> [u8; 256] {
+    let mut cdf = [0u64; 256];
+    let mut sum = 0;
+    for (i, &count) in hist.iter().enumerate() {
+        sum += count as u64;
+        cdf[i] = sum;
+    }
+    let cdf_min = cdf.iter().copied().find(|&c| c > 0).unwrap_or(0);
+
+    let mut table = [0u8; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        *entry = if sum == cdf_min {
+            i as u8
+        } else {
+            ((cdf[i].saturating_sub(cdf_min)) as f64 / (sum - cdf_min) as f64 * 255.0).round() as u8
+        };
+    }
+    table
+}
+
+pub fn equalize_histogram(img: &mut DynamicImage, channel: EqualizeMode) {
+    let mut rgba = img.to_rgba8();
+    match channel {
+        EqualizeMode::Y => {
+            let mut hist = [0u32; 256];
+            for p in rgba.pixels() {
+                hist[rgb_to_ycbcr(p[0], p[1], p[2]).0.round() as usize] += 1;
+            }
+            let table = equalization_table(&hist);
+            for p in rgba.pixels_mut() {
+                let (y, cb, cr) = rgb_to_ycbcr(p[0], p[1], p[2]);
+                let (r, g, b) = ycbcr_to_rgb(table[y.round() as usize] as f32, cb, cr);
+                p.0 = [r, g, b, p[3]];
+            }
+        }
+        _ => {
+            let channels: &[usize] = match channel {
+                EqualizeMode::R => &[0],
+                EqualizeMode::G => &[1],
+                EqualizeMode::B => &[2],
+                _ => &[0, 1, 2],
+            };
+            for &c in channels {
+                let mut hist = [0u32; 256];
+                for p in rgba.pixels() {
+                    hist[p[c] as usize] += 1;
+                }
+                let table = equalization_table(&hist);
+                for p in rgba.pixels_mut() {
+                    p[c] = table[p[c] as usize];
+                }
+            }
+        }
+    }
+    *img = DynamicImage::ImageRgba8(rgba);
+}
@@ -0,0 +1,20 @@
+use image::{DynamicImage, GenericImageView};
+use rand::{RngExt, SeedableRng, rngs::StdRng};
+
+/// Shifts each row sideways by a random amount with the given `probability`, pixels pushed off
+/// one edge come back in at the other. The same `seed` always glitches the same rows
+pub fn glitch(img: &mut DynamicImage, probability: f32, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (width, _) = img.dimensions();
+    if width < 2 {
+        return;
+    }
+    let mut out = img.to_rgba8();
+    for row in out.chunks_exact_mut(width as usize * 4) {
+        if rng.random::<f32>() < probability {
+            let offset = rng.random_range(1..width) as usize;
+            row.rotate_right(offset * 4);
+        }
+    }
+    *img = DynamicImage::ImageRgba8(out);
+}
@@ -0,0 +1,19 @@
+use image::{DynamicImage, Rgba};
+use rand::{RngExt, SeedableRng, rngs::StdRng};
+
+/// Adds gaussian noise with a standard deviation of `strength` (in 0..=255 units) to every
+/// pixel. The same `seed` always gives the same grain
+pub fn add_grain(img: &mut DynamicImage, strength: f32, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut out = img.to_rgba8();
+    for p in out.pixels_mut() {
+        /* Box-Muller, one sample per pixel so the grain is monochrome like film */
+        let u1: f32 = rng.random::<f32>().max(f32::MIN_POSITIVE);
+        let u2: f32 = rng.random();
+        let noise = (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos() * strength;
+        let Rgba([r, g, b, a]) = *p;
+        let add = |c: u8| (c as f32 + noise).round().clamp(0.0, 255.0) as u8;
+        *p = Rgba([add(r), add(g), add(b), a]);
+    }
+    *img = DynamicImage::ImageRgba8(out);
+}
@@ -0,0 +1,24 @@
+use image::{DynamicImage, Rgba};
+
+use super::color::{hsl_to_rgb, rgb_to_hsl};
+
+/// Rotates the hue by `hue_shift` degrees and multiplies saturation and lightness
+pub fn adjust_hsl(
+    img: &DynamicImage,
+    hue_shift: f32,
+    saturation: f32,
+    lightness: f32,
+) -> DynamicImage {
+    let mut out = img.to_rgba8();
+    for p in out.pixels_mut() {
+        let Rgba([r, g, b, a]) = *p;
+        let (h, s, l) = rgb_to_hsl(r, g, b);
+        let (r, g, b) = hsl_to_rgb(
+            h + hue_shift,
+            (s * saturation).clamp(0.0, 1.0),
+            (l * lightness).clamp(0.0, 1.0),
+        );
+        *p = Rgba([r, g, b, a]);
+    }
+    DynamicImage::ImageRgba8(out)
+}
@@ -0,0 +1,29 @@
+use std::f32::consts::TAU;
+
+use charcoal_core::sample_bilinear;
+use image::{DynamicImage, GenericImageView, RgbaImage};
+
+/// Repeats the wedge of the image right of its center `segments` times around the center, every
+/// other copy mirrored so neighbouring copies meet without a seam. With an odd number of segments
+/// the first and last copy still meet at a seam
+pub fn kaleidoscope(img: &DynamicImage, segments: u32) -> DynamicImage {
+    let (width, height) = img.dimensions();
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+    let wedge = TAU / segments as f32;
+    let mut out = RgbaImage::new(width, height);
+    /* Inverse mapping in polar coordinates: fold the angle of every output pixel into the first
+     * wedge and sample there at the same distance from the center */
+    for (x, y, p) in out.enumerate_pixels_mut() {
+        let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+        let radius = dx.hypot(dy);
+        let angle = dy.atan2(dx).rem_euclid(TAU);
+        let segment = (angle / wedge) as u32;
+        let mut folded = angle - segment as f32 * wedge;
+        if segment % 2 == 1 {
+            folded = wedge - folded;
+        }
+        let (sx, sy) = (cx + radius * folded.cos(), cy + radius * folded.sin());
+        *p = sample_bilinear(img, sx - 0.5, sy - 0.5);
+    }
+    DynamicImage::ImageRgba8(out)
+}
@@ -0,0 +1,45 @@
+use image::{DynamicImage, GenericImageView};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MirrorAxis {
+    /* The left half is reflected onto the right */
+    X,
+    /* The top half is reflected onto the bottom */
+    Y,
+}
+
+impl MirrorAxis {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "x" => Ok(MirrorAxis::X),
+            "y" => Ok(MirrorAxis::Y),
+            _ => Err(format!("Unknown mirror axis: {}", s)),
+        }
+    }
+}
+
+/// Makes the image symmetric by replacing the right (or bottom) half with a reflection of the
+/// left (or top) half
+pub fn mirror(img: &DynamicImage, axis: MirrorAxis) -> DynamicImage {
+    let (width, height) = img.dimensions();
+    let mut out = img.to_rgba8();
+    match axis {
+        MirrorAxis::X => {
+            for y in 0..height {
+                for x in width.div_ceil(2)..width {
+                    let p = *out.get_pixel(width - 1 - x, y);
+                    out.put_pixel(x, y, p);
+                }
+            }
+        }
+        MirrorAxis::Y => {
+            for y in height.div_ceil(2)..height {
+                for x in 0..width {
+                    let p = *out.get_pixel(x, height - 1 - y);
+                    out.put_pixel(x, y, p);
+                }
+            }
+        }
+    }
+    DynamicImage::ImageRgba8(out)
+}
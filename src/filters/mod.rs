@@ -0,0 +1,385 @@
+/* Filters operate on a whole image and return a new one, they are applied before rendering */
+use std::sync::Arc;
+
+use image::{DynamicImage, Rgb};
+
+use crate::chessboard;
+
+mod autocrop;
+mod blur;
+mod cartoon;
+mod channels;
+mod chromatic;
+mod clahe;
+mod color;
+mod crt;
+mod datamosh;
+mod denoise;
+mod drop_shadow;
+mod edge;
+mod equalize;
+mod glitch;
+mod grain;
+mod hsl;
+mod kaleidoscope;
+mod mirror;
+mod mosaic;
+mod pad;
+mod pipeline;
+mod pixel_sort;
+mod posterize;
+mod round_corners;
+mod split_tone;
+mod vhs;
+mod vibrance;
+mod vignette;
+
+pub use autocrop::auto_crop;
+pub use blur::{gaussian_blur, unsharp_mask};
+pub use cartoon::cartoon;
+pub use channels::{Channel, isolate_channel};
+pub use chromatic::chromatic_aberration;
+pub use clahe::clahe;
+pub use color::hsv_to_rgb;
+pub use crt::{barrel_distortion, glow, phosphor_mask, scanlines};
+pub use datamosh::datamosh;
+pub use denoise::median_filter;
+pub use drop_shadow::drop_shadow;
+pub use edge::{EdgeAlgorithm, detect_edges};
+pub use equalize::{EqualizeMode, equalize_histogram};
+pub use glitch::glitch;
+pub use grain::add_grain;
+pub use hsl::adjust_hsl;
+pub use kaleidoscope::kaleidoscope;
+pub use mirror::{MirrorAxis, mirror};
+pub use mosaic::{TileLibrary, photomosaic};
+pub use pad::{Gravity, pad_to_square};
+pub use pipeline::FilterPipeline;
+pub use pixel_sort::{SortAxis, SortKey, sort_pixels};
+pub use posterize::posterize;
+pub use round_corners::round_corners;
+pub use split_tone::split_tone;
+pub use vhs::vhs_filter;
+pub use vibrance::vibrance;
+pub use vignette::vignette;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Cartoon {
+        k: u32,
+    },
+    Datamosh {
+        quality: u8,
+    },
+    Denoise {
+        strength: u32,
+    },
+    Channel(Channel),
+    Equalize(EqualizeMode),
+    Clahe {
+        clip_limit: f32,
+        tile_size: u32,
+    },
+    SortPixels {
+        key: SortKey,
+        axis: SortAxis,
+        threshold: Option<(u8, u8)>,
+    },
+    /* Hue shift in degrees, saturation and lightness are factors */
+    Hsl {
+        hue_shift: f32,
+        saturation: f32,
+        lightness: f32,
+    },
+    /* Hues in degrees */
+    SplitTone {
+        shadow_hue: f32,
+        highlight_hue: f32,
+    },
+    /* Shared so cloning the filter doesn't copy every thumbnail */
+    Mosaic(Arc<TileLibrary>),
+    Vhs,
+    /* Red and blue are moved this many pixels apart from green */
+    ChromaticAberration {
+        px: u32,
+    },
+    Barrel {
+        strength: f32,
+    },
+    /* Every other row is darkened by this factor */
+    Scanlines {
+        darken: f32,
+    },
+    PhosphorMask,
+    Glow {
+        sigma: f32,
+    },
+    /* Barrel, scanlines, phosphor mask and glow in one go */
+    Crt,
+    Kaleidoscope {
+        segments: u32,
+    },
+    EdgeDetect(EdgeAlgorithm),
+    GaussianBlur {
+        radius: u32,
+    },
+    UnsharpMask {
+        radius: u32,
+        amount: f32,
+        threshold: u8,
+    },
+    Vignette {
+        strength: f32,
+    },
+    /* Borders that differ from their median color by at most this much are cropped */
+    AutoCrop {
+        threshold: u8,
+    },
+    /* The padding is transparent unless a color is given */
+    PadToSquare {
+        background: [u8; 4],
+        gravity: Gravity,
+    },
+    /* Corner radius in pixels */
+    RoundCorners {
+        radius: u32,
+    },
+    /* Offset and blur radius in pixels */
+    DropShadow {
+        offset: (i32, i32),
+        blur: u32,
+        color: Rgb<u8>,
+    },
+    /* Values per channel, 2 to 8 */
+    Posterize {
+        levels: u8,
+    },
+}
+
+/* Used by `sharpen <amount>` without a radius and threshold */
+const SHARPEN_RADIUS: u32 = 2;
+const SHARPEN_THRESHOLD: u8 = 0;
+
+/* Used by `autocrop` without a threshold, enough to get past JPEG noise in a flat border */
+pub const AUTO_CROP_THRESHOLD: u8 = 16;
+
+/* The steps of the crt filter, in order */
+const CRT: &[Filter] = &[
+    Filter::Barrel { strength: 0.2 },
+    Filter::Scanlines { darken: 0.3 },
+    Filter::PhosphorMask,
+    Filter::Glow { sigma: 1.5 },
+];
+
+/// Applies `filters` one after the other
+pub fn compose_filters(filters: &[Filter], img: &DynamicImage) -> DynamicImage {
+    filters
+        .iter()
+        .fold(img.clone(), |img, filter| filter.apply(&img))
+}
+
+impl Filter {
+    /// Parses a filter from its name followed by its arguments, e.g. `["cartoon", "8"]`
+    pub fn parse(args: &[&str]) -> Result<Self, String> {
+        match args {
+            ["cartoon"] => Ok(Filter::Cartoon { k: 8 }),
+            ["cartoon", k] => match k.parse::<u32>() {
+                Ok(k) if k >= 1 => Ok(Filter::Cartoon { k }),
+                _ => Err(format!("Invalid number of colors: {}", k)),
+            },
+            ["datamosh", quality] => match quality.parse::<u8>() {
+                Ok(quality @ 1..=10) => Ok(Filter::Datamosh { quality }),
+                _ => Err(format!("Datamosh quality must be 1-10: {}", quality)),
+            },
+            ["denoise", strength] => match strength.parse::<u32>() {
+                Ok(strength @ 1..) => Ok(Filter::Denoise { strength }),
+                _ => Err(format!("Invalid denoise strength: {}", strength)),
+            },
+            ["channels", channel] => Channel::parse(channel).map(Filter::Channel),
+            ["equalize", mode] => EqualizeMode::parse(mode).map(Filter::Equalize),
+            ["clahe", clip_limit, tile_size] => {
+                match (clip_limit.parse::<f32>(), tile_size.parse::<u32>()) {
+                    (Ok(clip_limit), Ok(tile_size)) if clip_limit > 0.0 && tile_size > 0 => {
+                        Ok(Filter::Clahe {
+                            clip_limit,
+                            tile_size,
+                        })
+                    }
+                    _ => Err("Usage: clahe <clip-limit> <tile-size>".to_string()),
+                }
+            }
+            ["hsl", hue_shift, saturation, lightness] => match (
+                hue_shift.parse::<f32>(),
+                saturation.parse::<f32>(),
+                lightness.parse::<f32>(),
+            ) {
+                (Ok(hue_shift), Ok(saturation), Ok(lightness))
+                    if saturation >= 0.0 && lightness >= 0.0 =>
+                {
+                    Ok(Filter::Hsl {
+                        hue_shift,
+                        saturation,
+                        lightness,
+                    })
+                }
+                _ => Err("Usage: hsl <hue-shift> <saturation> <lightness>".to_string()),
+            },
+            ["split-tone", shadow_hue, highlight_hue] => {
+                match (shadow_hue.parse::<f32>(), highlight_hue.parse::<f32>()) {
+                    (Ok(shadow_hue), Ok(highlight_hue)) => Ok(Filter::SplitTone {
+                        shadow_hue,
+                        highlight_hue,
+                    }),
+                    _ => Err("Usage: split-tone <shadow-hue> <highlight-hue>".to_string()),
+                }
+            }
+            ["vhs"] => Ok(Filter::Vhs),
+            ["crt"] => Ok(Filter::Crt),
+            ["barrel", strength] => match strength.parse::<f32>() {
+                Ok(strength) => Ok(Filter::Barrel { strength }),
+                _ => Err(format!("Invalid barrel strength: {}", strength)),
+            },
+            ["scanlines", darken] => match darken.parse::<f32>() {
+                Ok(darken) if (0.0..=1.0).contains(&darken) => Ok(Filter::Scanlines { darken }),
+                _ => Err(format!("Scanline darkening must be 0.0 to 1.0: {}", darken)),
+            },
+            ["phosphor"] => Ok(Filter::PhosphorMask),
+            ["glow", sigma] => match sigma.parse::<f32>() {
+                Ok(sigma) if sigma > 0.0 => Ok(Filter::Glow { sigma }),
+                _ => Err(format!("Invalid glow radius: {}", sigma)),
+            },
+            ["ca", px] => match px.parse::<u32>() {
+                Ok(px) => Ok(Filter::ChromaticAberration { px }),
+                _ => Err(format!("Invalid channel offset: {}", px)),
+            },
+            ["kaleidoscope", segments] => match segments.parse::<u32>() {
+                Ok(segments @ 2..) => Ok(Filter::Kaleidoscope { segments }),
+                _ => Err(format!(
+                    "Kaleidoscope needs at least 2 segments: {}",
+                    segments
+                )),
+            },
+            ["edge", algorithm @ ..] => EdgeAlgorithm::parse(algorithm).map(Filter::EdgeDetect),
+            ["blur", radius] => match radius.parse::<u32>() {
+                Ok(radius @ 1..) => Ok(Filter::GaussianBlur { radius }),
+                _ => Err(format!("Invalid blur radius: {}", radius)),
+            },
+            ["sharpen", amount, rest @ ..] => {
+                let usage = || "Usage: sharpen <amount> [<radius> <threshold>]".to_string();
+                let (radius, threshold) = match rest {
+                    [] => (Ok(SHARPEN_RADIUS), Ok(SHARPEN_THRESHOLD)),
+                    [radius, threshold] => (radius.parse::<u32>(), threshold.parse::<u8>()),
+                    _ => return Err(usage()),
+                };
+                match (amount.parse::<f32>(), radius, threshold) {
+                    (Ok(amount), Ok(radius @ 1..), Ok(threshold)) if amount > 0.0 => {
+                        Ok(Filter::UnsharpMask {
+                            radius,
+                            amount,
+                            threshold,
+                        })
+                    }
+                    _ => Err(usage()),
+                }
+            }
+            ["vignette", strength] => match strength.parse::<f32>() {
+                Ok(strength) if (0.0..=1.0).contains(&strength) => {
+                    Ok(Filter::Vignette { strength })
+                }
+                _ => Err(format!(
+                    "Vignette strength must be 0.0 to 1.0: {}",
+                    strength
+                )),
+            },
+            ["posterize", levels] => match levels.parse::<u8>() {
+                Ok(levels @ 2..=8) => Ok(Filter::Posterize { levels }),
+                _ => Err(format!("Posterize levels must be 2-8: {}", levels)),
+            },
+            ["round-corners", radius] => match radius.parse::<u32>() {
+                Ok(radius) => Ok(Filter::RoundCorners { radius }),
+                _ => Err(format!("Invalid corner radius: {}", radius)),
+            },
+            ["drop-shadow", x, y, blur, color] => {
+                match (x.parse::<i32>(), y.parse::<i32>(), blur.parse::<u32>()) {
+                    (Ok(x), Ok(y), Ok(blur)) => Ok(Filter::DropShadow {
+                        offset: (x, y),
+                        blur,
+                        color: chessboard::parse_color(color)?,
+                    }),
+                    _ => Err("Usage: drop-shadow <x> <y> <blur> <color>".to_string()),
+                }
+            }
+            ["autocrop"] => Ok(Filter::AutoCrop {
+                threshold: AUTO_CROP_THRESHOLD,
+            }),
+            ["autocrop", threshold] => match threshold.parse::<u8>() {
+                Ok(threshold) => Ok(Filter::AutoCrop { threshold }),
+                _ => Err(format!("Crop threshold must be 0-255: {}", threshold)),
+            },
+            [name, ..] => Err(format!("Unknown filter: {}", name)),
+            [] => Err("Usage: filter <name> [args...]".to_string()),
+        }
+    }
+
+    pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
+        match self {
+            Filter::Cartoon { k } => cartoon(img, *k),
+            Filter::Datamosh { quality } => datamosh(img, *quality),
+            /* Strength 1 is a 3x3 window, 2 is 5x5 and so on */
+            Filter::Denoise { strength } => median_filter(img, *strength),
+            Filter::Channel(channel) => isolate_channel(img, *channel),
+            Filter::Clahe {
+                clip_limit,
+                tile_size,
+            } => clahe(img, *clip_limit, *tile_size),
+            Filter::Equalize(mode) => {
+                let mut out = img.clone();
+                equalize_histogram(&mut out, *mode);
+                out
+            }
+            Filter::SortPixels {
+                key,
+                axis,
+                threshold,
+            } => sort_pixels(img, *key, *axis, *threshold),
+            Filter::Hsl {
+                hue_shift,
+                saturation,
+                lightness,
+            } => adjust_hsl(img, *hue_shift, *saturation, *lightness),
+            Filter::SplitTone {
+                shadow_hue,
+                highlight_hue,
+            } => split_tone(img, *shadow_hue, *highlight_hue),
+            Filter::Mosaic(library) => photomosaic(img, library),
+            Filter::Vhs => vhs_filter(img),
+            Filter::ChromaticAberration { px } => chromatic_aberration(img, *px),
+            Filter::Barrel { strength } => barrel_distortion(img, *strength),
+            Filter::Scanlines { darken } => scanlines(img, *darken),
+            Filter::PhosphorMask => phosphor_mask(img),
+            Filter::Glow { sigma } => glow(img, *sigma),
+            Filter::Crt => compose_filters(CRT, img),
+            Filter::Kaleidoscope { segments } => kaleidoscope(img, *segments),
+            Filter::EdgeDetect(algorithm) => detect_edges(img, *algorithm, false),
+            Filter::GaussianBlur { radius } => gaussian_blur(img, *radius),
+            Filter::UnsharpMask {
+                radius,
+                amount,
+                threshold,
+            } => unsharp_mask(img, *radius, *amount, *threshold),
+            Filter::Vignette { strength } => vignette(img, *strength),
+            Filter::AutoCrop { threshold } => auto_crop(img, *threshold),
+            Filter::PadToSquare {
+                background,
+                gravity,
+            } => pad_to_square(img, *background, *gravity),
+            Filter::RoundCorners { radius } => round_corners(img, *radius),
+            Filter::DropShadow {
+                offset,
+                blur,
+                color,
+            } => drop_shadow(img, *offset, *blur, *color),
+            Filter::Posterize { levels } => posterize(img, *levels),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::path::Path;
+
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage, imageops::FilterType};
+
+/* Thumbnails used as tiles, all resized to `tile_px` squares */
+#[derive(Debug, PartialEq)]
+pub struct TileLibrary {
+    tile_px: u32,
+    tiles: Vec<(Rgb<u8>, RgbImage)>,
+}
+
+impl TileLibrary {
+    /// Loads every image in `dir`, files that can't be decoded are skipped
+    pub fn load(dir: &Path, tile_px: u32) -> Result<Self, String> {
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| format!("Can't read tile directory {}: {}", dir.display(), e))?;
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .collect();
+        /* Sorted so ties between equally good tiles are broken the same way every run */
+        paths.sort();
+
+        let tiles: Vec<_> = paths
+            .iter()
+            .filter_map(|path| image::open(path).ok())
+            .map(|thumb| {
+                let tile = thumb
+                    .resize_to_fill(tile_px, tile_px, FilterType::Triangle)
+                    .to_rgb8();
+                (average(&tile, 0, 0, tile_px, tile_px), tile)
+            })
+            .collect();
+        if tiles.is_empty() {
+            return Err(format!("No images found in {}", dir.display()));
+        }
+        Ok(TileLibrary { tile_px, tiles })
+    }
+
+    fn closest(&self, color: Rgb<u8>) -> &RgbImage {
+        let dist = |c: &Rgb<u8>| {
+            (0..3)
+                .map(|i| (c[i] as i32 - color[i] as i32).pow(2))
+                .sum::<i32>()
+        };
+        self.tiles
+            .iter()
+            .min_by_key(|(avg, _)| dist(avg))
+            .map(|(_, tile)| tile)
+            .expect("library is never empty")
+    }
+}
+
+/* Average color of the `w`x`h` block at (x0, y0) */
+fn average(img: &RgbImage, x0: u32, y0: u32, w: u32, h: u32) -> Rgb<u8> {
+    let mut sum = [0u64; 3];
+    for y in y0..y0 + h {
+        for x in x0..x0 + w {
+            let p = img.get_pixel(x, y);
+            for c in 0..3 {
+                sum[c] += p[c] as u64;
+            }
+        }
+    }
+    let n = (w as u64 * h as u64).max(1);
+    Rgb(sum.map(|s| (s / n) as u8))
+}
+
+/// Replaces every block of the image with the tile whose average color is closest
+pub fn photomosaic(img: &DynamicImage, library: &TileLibrary) -> DynamicImage {
+    let src = img.to_rgb8();
+    let (width, height) = img.dimensions();
+    let size = library.tile_px;
+    let mut out = RgbImage::new(width, height);
+
+    for by in (0..height).step_by(size as usize) {
+        for bx in (0..width).step_by(size as usize) {
+            /* Blocks at the right and bottom edge may be cut off */
+            let w = size.min(width - bx);
+            let h = size.min(height - by);
+            let tile = library.closest(average(&src, bx, by, w, h));
+            for y in 0..h {
+                for x in 0..w {
+                    out.put_pixel(bx + x, by + y, *tile.get_pixel(x, y));
+                }
+            }
+        }
+    }
+    DynamicImage::ImageRgb8(out)
+}
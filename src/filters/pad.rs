@@ -0,0 +1,49 @@
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage, imageops};
+
+/// Where the image goes in the padded square
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Gravity {
+    #[default]
+    Center,
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Gravity {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "center" => Ok(Gravity::Center),
+            "north" => Ok(Gravity::North),
+            "south" => Ok(Gravity::South),
+            "east" => Ok(Gravity::East),
+            "west" => Ok(Gravity::West),
+            _ => Err(format!(
+                "Unknown gravity: {} (expected center, north, south, east or west)",
+                s
+            )),
+        }
+    }
+}
+
+/// Pads the shorter side with `bg` so the image becomes square. `gravity` only matters along
+/// the padded side, north and south for wide images and east and west for tall ones
+pub fn pad_to_square(img: &DynamicImage, bg: [u8; 4], gravity: Gravity) -> DynamicImage {
+    let (w, h) = img.dimensions();
+    let size = w.max(h);
+    let (free_x, free_y) = (size - w, size - h);
+    let x = match gravity {
+        Gravity::West => 0,
+        Gravity::East => free_x,
+        _ => free_x / 2,
+    };
+    let y = match gravity {
+        Gravity::North => 0,
+        Gravity::South => free_y,
+        _ => free_y / 2,
+    };
+    let mut out = RgbaImage::from_pixel(size, size, Rgba(bg));
+    imageops::replace(&mut out, &img.to_rgba8(), x as i64, y as i64);
+    DynamicImage::ImageRgba8(out)
+}
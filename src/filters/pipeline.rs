@@ -0,0 +1,57 @@
+use image::DynamicImage;
+
+use super::Filter;
+
+/// Filters that are run on every redraw instead of being applied once, each one on the output
+/// of the one before. Unlike applied filters they can be changed later on. Every filter is
+/// kept with the name it was added under, for listing them
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FilterPipeline(Vec<(String, Filter)>);
+
+impl FilterPipeline {
+    pub fn push(&mut self, name: String, filter: Filter) {
+        self.0.push((name, filter));
+    }
+
+    /// Removes the filter at `index`, returns false if there is none
+    pub fn remove(&mut self, index: usize) -> bool {
+        if index >= self.0.len() {
+            return false;
+        }
+        self.0.remove(index);
+        true
+    }
+
+    /// Moves the filter at `from` to `to`, shifting the ones in between. Returns false if
+    /// either index is out of range
+    pub fn move_filter(&mut self, from: usize, to: usize) -> bool {
+        if from >= self.0.len() || to >= self.0.len() {
+            return false;
+        }
+        let filter = self.0.remove(from);
+        self.0.insert(to, filter);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
+        self.0
+            .iter()
+            .fold(img.clone(), |img, (_, filter)| filter.apply(&img))
+    }
+}
@@ -0,0 +1,108 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use super::color::{luma, rgb_to_hsv};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Hue,
+    Luma,
+    Saturation,
+}
+
+impl SortKey {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "hue" => Ok(SortKey::Hue),
+            "luma" => Ok(SortKey::Luma),
+            "saturation" => Ok(SortKey::Saturation),
+            _ => Err(format!("Unknown sort key: {}", s)),
+        }
+    }
+
+    fn of(&self, p: &Rgba<u8>) -> f32 {
+        let [r, g, b, _] = p.0;
+        match self {
+            SortKey::Hue => rgb_to_hsv(r, g, b).0,
+            SortKey::Luma => luma(r, g, b),
+            SortKey::Saturation => rgb_to_hsv(r, g, b).1,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SortAxis {
+    #[default]
+    Row,
+    Column,
+}
+
+impl SortAxis {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "row" => Ok(SortAxis::Row),
+            "column" => Ok(SortAxis::Column),
+            _ => Err(format!("Unknown sort axis: {}", s)),
+        }
+    }
+}
+
+/// Sorts the pixels of every row (or column) by `key`, a classic glitch-art effect.
+/// With a `threshold`, only runs of pixels whose luma lies within `low..=high` get sorted, the
+/// other pixels stay in place and split the line into separate spans
+pub fn sort_pixels(
+    img: &DynamicImage,
+    key: SortKey,
+    axis: SortAxis,
+    threshold: Option<(u8, u8)>,
+) -> DynamicImage {
+    let mut out = img.to_rgba8();
+    let (w, h) = out.dimensions();
+    let (lines, len) = match axis {
+        SortAxis::Row => (h, w),
+        SortAxis::Column => (w, h),
+    };
+    let coords = |line: u32, i: u32| match axis {
+        SortAxis::Row => (i, line),
+        SortAxis::Column => (line, i),
+    };
+
+    for line in 0..lines {
+        let mut pixels: Vec<Rgba<u8>> = (0..len)
+            .map(|i| {
+                let (x, y) = coords(line, i);
+                *out.get_pixel(x, y)
+            })
+            .collect();
+        match threshold {
+            None => pixels.sort_by(|a, b| key.of(a).total_cmp(&key.of(b))),
+            Some((low, high)) => {
+                let in_range = |p: &Rgba<u8>| {
+                    let l = luma(p[0], p[1], p[2]);
+                    l >= low as f32 && l <= high as f32
+                };
+                let mut start = 0;
+                while start < pixels.len() {
+                    if !in_range(&pixels[start]) {
+                        start += 1;
+                        continue;
+                    }
+                    let end = pixels[start..]
+                        .iter()
+                        .position(|p| !in_range(p))
+                        .map_or(pixels.len(), |n| start + n);
+                    pixels[start..end].sort_by(|a, b| key.of(a).total_cmp(&key.of(b)));
+                    start = end;
+                }
+            }
+        }
+        write_line(&mut out, &pixels, |i| coords(line, i));
+    }
+    DynamicImage::ImageRgba8(out)
+}
+
+fn write_line(img: &mut RgbaImage, pixels: &[Rgba<u8>], coords: impl Fn(u32) -> (u32, u32)) {
+    for (i, p) in pixels.iter().enumerate() {
+        let (x, y) = coords(i as u32);
+        img.put_pixel(x, y, *p);
+    }
+}
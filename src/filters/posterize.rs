@@ -0,0 +1,36 @@
+use image::{DynamicImage, Rgba};
+
+/// Rounds every channel to the nearest of `levels` evenly spaced values between 0 and 255,
+/// which gives large areas of flat color. `levels` must be at least 2
+pub fn posterize(img: &DynamicImage, levels: u8) -> DynamicImage {
+    let step = 255.0 / (levels - 1) as f32;
+    let quantize = |c: u8| ((c as f32 / step).round() * step).round() as u8;
+    let mut out = img.to_rgba8();
+    for p in out.pixels_mut() {
+        let Rgba([r, g, b, a]) = *p;
+        *p = Rgba([quantize(r), quantize(g), quantize(b), a]);
+    }
+    DynamicImage::ImageRgba8(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn two_levels_are_black_and_white() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([0x80, 0x7f, 0, 200])));
+        let out = posterize(&img, 2).to_rgba8();
+        assert_eq!(out.get_pixel(0, 0), &Rgba([255, 0, 0, 200]));
+    }
+
+    #[test]
+    fn every_value_lands_on_a_level() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(256, 1, |x, _| {
+            Rgba([x as u8, x as u8, x as u8, 255])
+        }));
+        let out = posterize(&img, 4).to_rgba8();
+        assert!(out.pixels().all(|p| [0, 85, 170, 255].contains(&p[0])));
+    }
+}
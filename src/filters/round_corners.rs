@@ -0,0 +1,22 @@
+use image::{DynamicImage, GenericImageView};
+
+/// Makes the corners transparent outside of circles with `radius` pixels, adding an alpha
+/// channel if needed. The edge of the circles is antialiased, and the radius is limited to
+/// half the shorter side
+pub fn round_corners(img: &DynamicImage, radius: u32) -> DynamicImage {
+    let (w, h) = img.dimensions();
+    let r = radius.min(w / 2).min(h / 2) as f32;
+    let mut out = img.to_rgba8();
+    for (x, y, p) in out.enumerate_pixels_mut() {
+        /* Distance from the center of the nearest corner's circle, along each axis, if the
+         * pixel is in a corner at all */
+        let dx = (r - (x as f32 + 0.5)).max((x as f32 + 0.5) - (w as f32 - r));
+        let dy = (r - (y as f32 + 0.5)).max((y as f32 + 0.5) - (h as f32 - r));
+        if dx <= 0.0 || dy <= 0.0 {
+            continue;
+        }
+        let coverage = (r - dx.hypot(dy) + 0.5).clamp(0.0, 1.0);
+        p[3] = (p[3] as f32 * coverage).round() as u8;
+    }
+    DynamicImage::ImageRgba8(out)
+}
@@ -0,0 +1,37 @@
+use image::{DynamicImage, Rgba};
+
+use super::color::{hsv_to_rgb, luma};
+
+/* How far pixels are pulled towards the tint */
+const STRENGTH: f32 = 0.5;
+
+/// Tints the shadows with `shadow_hue` and the highlights with `highlight_hue` (both in degrees),
+/// fading from one to the other by luma. The brightness of every pixel stays the same
+pub fn split_tone(img: &DynamicImage, shadow_hue: f32, highlight_hue: f32) -> DynamicImage {
+    let shadow = hsv_to_rgb(shadow_hue, 1.0, 1.0);
+    let highlight = hsv_to_rgb(highlight_hue, 1.0, 1.0);
+    let mut out = img.to_rgba8();
+
+    for p in out.pixels_mut() {
+        let Rgba([r, g, b, a]) = *p;
+        let y = luma(r, g, b);
+        let t = y / 255.0;
+        let mix = |s: u8, h: u8| s as f32 * (1.0 - t) + h as f32 * t;
+        let tint = [
+            mix(shadow.0, highlight.0),
+            mix(shadow.1, highlight.1),
+            mix(shadow.2, highlight.2),
+        ];
+
+        /* Scale the tint to the luma of the pixel, so only the color changes */
+        let tint_y = 0.299 * tint[0] + 0.587 * tint[1] + 0.114 * tint[2];
+        let scale = if tint_y > 0.0 { y / tint_y } else { 0.0 };
+        let blend = |orig: u8, tint: f32| {
+            (orig as f32 * (1.0 - STRENGTH) + tint * scale * STRENGTH)
+                .round()
+                .clamp(0.0, 255.0) as u8
+        };
+        *p = Rgba([blend(r, tint[0]), blend(g, tint[1]), blend(b, tint[2]), a]);
+    }
+    DynamicImage::ImageRgba8(out)
+}
@@ -0,0 +1,37 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use super::{chromatic::chromatic_aberration, crt::scanlines, grain::add_grain};
+
+/* How far red is shifted left and blue right, in pixels */
+const CHANNEL_SHIFT: u32 = 2;
+/* Every other row is darkened by this much */
+const SCANLINE_DARKEN: f32 = 0.2;
+const GRAIN_STRENGTH: f32 = 8.0;
+const GRAIN_SEED: u64 = 0x5648_5321;
+
+/// Makes the image look like it was played from a worn VHS tape: red and blue are pulled apart
+/// horizontally, the picture is smeared a little vertically, every other row is darker and
+/// grain is added on top
+pub fn vhs_filter(img: &DynamicImage) -> DynamicImage {
+    let shifted = chromatic_aberration(img, CHANNEL_SHIFT).to_rgba8();
+    let (width, height) = shifted.dimensions();
+
+    /* 1-2-1 blur along Y, done before the scan lines so they stay sharp */
+    let mut out = RgbaImage::new(width, height);
+    for (x, y, p) in out.enumerate_pixels_mut() {
+        let above = shifted.get_pixel(x, y.saturating_sub(1));
+        let here = shifted.get_pixel(x, y);
+        let below = shifted.get_pixel(x, (y + 1).min(height - 1));
+        *p = Rgba(std::array::from_fn(|c| {
+            if c == 3 {
+                here[c]
+            } else {
+                ((above[c] as f32 + 2.0 * here[c] as f32 + below[c] as f32) / 4.0).round() as u8
+            }
+        }));
+    }
+
+    let mut out = scanlines(&DynamicImage::ImageRgba8(out), SCANLINE_DARKEN);
+    add_grain(&mut out, GRAIN_STRENGTH, GRAIN_SEED);
+    out
+}
@@ -0,0 +1,19 @@
+use image::{DynamicImage, Rgba};
+
+/// Saturates muted colors by up to `amount` (-1.0..=1.0) while leaving already saturated ones
+/// mostly alone. How saturated a pixel is comes from the distance between its largest channel
+/// and the average of all three
+pub fn vibrance(img: &DynamicImage, amount: f32) -> DynamicImage {
+    let mut out = img.to_rgba8();
+    for p in out.pixels_mut() {
+        let Rgba([r, g, b, a]) = *p;
+        let max = r.max(g).max(b) as f32;
+        let avg = (r as f32 + g as f32 + b as f32) / 3.0;
+        /* max - avg is at most 2/3 of the range, for fully saturated primaries */
+        let saturation = (max - avg) / (255.0 * 2.0 / 3.0);
+        let boost = 1.0 + amount * (1.0 - saturation);
+        let push = |c: u8| (avg + (c as f32 - avg) * boost).round().clamp(0.0, 255.0) as u8;
+        *p = Rgba([push(r), push(g), push(b), a]);
+    }
+    DynamicImage::ImageRgba8(out)
+}
@@ -0,0 +1,21 @@
+use image::{DynamicImage, GenericImageView, Rgba};
+
+/// Darkens the image towards its corners. `strength` goes from 0.0 (no change) to 1.0 (black
+/// corners), the center always stays as it is
+pub fn vignette(img: &DynamicImage, strength: f32) -> DynamicImage {
+    let (width, height) = img.dimensions();
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+    let mut out = img.to_rgba8();
+    for (x, y, p) in out.enumerate_pixels_mut() {
+        /* 0 at the center and 1 in the corners, whatever the aspect ratio */
+        let nx = (x as f32 + 0.5 - cx) / cx;
+        let ny = (y as f32 + 0.5 - cy) / cy;
+        let d = (nx.hypot(ny) / std::f32::consts::SQRT_2).min(1.0);
+        /* Smoothstep, so the falloff starts gently instead of with a visible ring */
+        let factor = 1.0 - strength * d * d * (3.0 - 2.0 * d);
+        let Rgba([r, g, b, a]) = *p;
+        let dim = |c: u8| (c as f32 * factor).round() as u8;
+        *p = Rgba([dim(r), dim(g), dim(b), a]);
+    }
+    DynamicImage::ImageRgba8(out)
+}
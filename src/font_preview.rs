@@ -0,0 +1,90 @@
+use std::{fs, path::Path};
+
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont, point};
+use color_eyre::eyre::{Result, eyre};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+const PANGRAM: &str = "The quick brown fox jumps over the lazy dog";
+const SIZES: [f32; 6] = [12.0, 18.0, 24.0, 36.0, 48.0, 72.0];
+const MARGIN: u32 = 16;
+
+/* Width of `text` in pixels, including kerning */
+fn line_width<F: Font>(font: &impl ScaleFont<F>, text: &str) -> f32 {
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(prev) = previous {
+            width += font.kern(prev, id);
+        }
+        width += font.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+fn draw_line(canvas: &mut RgbaImage, font: &FontVec, size: f32, text: &str, top: f32) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut x = MARGIN as f32;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(prev) = previous {
+            x += scaled.kern(prev, id);
+        }
+        let glyph = id.with_scale_and_position(size, point(x, top + scaled.ascent()));
+        x += scaled.h_advance(id);
+        previous = Some(id);
+
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= canvas.width() as i32 || py >= canvas.height() as i32 {
+                return;
+            }
+            let v = (255.0 * (1.0 - coverage.clamp(0.0, 1.0))) as u8;
+            let pixel = canvas.get_pixel_mut(px as u32, py as u32);
+            pixel.0[0] = pixel.0[0].min(v);
+            pixel.0[1] = pixel.0[1].min(v);
+            pixel.0[2] = pixel.0[2].min(v);
+        });
+    }
+}
+
+/// Renders a font specimen: the pangram at several sizes followed by every printable ASCII
+/// character, black on white
+pub fn render_specimen(path: &Path) -> Result<DynamicImage> {
+    let font = FontVec::try_from_vec(fs::read(path)?)
+        .map_err(|_| eyre!("Not a TrueType/OpenType font: {}", path.display()))?;
+
+    let ascii: String = (0x20u8..0x7f).map(|b| b as char).collect();
+    let (first, second) = ascii.split_at(ascii.len() / 2);
+    let mut lines: Vec<(f32, &str)> = SIZES.iter().map(|&size| (size, PANGRAM)).collect();
+    lines.extend([(36.0, first), (36.0, second)]);
+
+    let line_height = |size: f32| {
+        let scaled = font.as_scaled(PxScale::from(size));
+        scaled.ascent() - scaled.descent() + scaled.line_gap()
+    };
+    let width = lines
+        .iter()
+        .map(|&(size, text)| line_width(&font.as_scaled(PxScale::from(size)), text))
+        .fold(0.0, f32::max);
+    let height: f32 = lines.iter().map(|&(size, _)| line_height(size)).sum();
+
+    let mut canvas = RgbaImage::from_pixel(
+        width.ceil() as u32 + 2 * MARGIN,
+        height.ceil() as u32 + 2 * MARGIN,
+        Rgba([255, 255, 255, 255]),
+    );
+    let mut top = MARGIN as f32;
+    for (size, text) in lines {
+        draw_line(&mut canvas, &font, size, text, top);
+        top += line_height(size);
+    }
+    Ok(DynamicImage::ImageRgba8(canvas))
+}
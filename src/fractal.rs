@@ -0,0 +1,159 @@
+/* Fractals rendered on the fly instead of being loaded from a file */
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use image::{DynamicImage, Rgb, RgbImage};
+
+use crate::filters::hsv_to_rgb;
+
+/* How much of the complex plane is visible across the width at zoom 1 */
+const BASE_SPAN: f64 = 3.5;
+/* Deeper zooms need more iterations before the boundary shows any detail */
+const BASE_ITERATIONS: u32 = 200;
+const ITERATIONS_PER_ZOOM: u32 = 50;
+/* Degrees of hue per iteration, the palette repeats every 360 / HUE_STEP iterations */
+const HUE_STEP: f64 = 8.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum FractalKind {
+    /* Every pixel is its own c, starting from z = 0 */
+    #[default]
+    Mandelbrot,
+    /* Every pixel is its own starting z, with the same c for all of them */
+    Julia {
+        c: (f64, f64),
+    },
+}
+
+impl FractalKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FractalKind::Mandelbrot => "mandelbrot",
+            FractalKind::Julia { .. } => "julia",
+        }
+    }
+
+    /* Where the interesting part is, the Mandelbrot set reaches further to the left */
+    pub fn default_center(&self) -> (f64, f64) {
+        match self {
+            FractalKind::Mandelbrot => (-0.5, 0.0),
+            FractalKind::Julia { .. } => (0.0, 0.0),
+        }
+    }
+}
+
+/* Which fractal is shown, the part of the complex plane and the size of the image it's drawn
+ * into */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FractalView {
+    pub kind: FractalKind,
+    pub center: (f64, f64),
+    pub zoom: f64,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for FractalView {
+    fn default() -> Self {
+        FractalView {
+            kind: FractalKind::default(),
+            center: FractalKind::default().default_center(),
+            zoom: 1.0,
+            width: 800,
+            height: 600,
+        }
+    }
+}
+
+impl FractalView {
+    /// Magnified by `factor` around the same center, below 1 zooms out
+    pub fn zoomed(self, factor: f64) -> Self {
+        FractalView {
+            zoom: self.zoom * factor,
+            ..self
+        }
+    }
+
+    /// Moved by `dx` and `dy` times the visible width, so a step looks the same at every zoom
+    pub fn panned(self, dx: f64, dy: f64) -> Self {
+        let span = BASE_SPAN / self.zoom;
+        FractalView {
+            center: (self.center.0 + dx * span, self.center.1 + dy * span),
+            ..self
+        }
+    }
+
+    /* Pixels are square, so the height shows proportionally less of the plane */
+    fn point_at(&self, x: u32, y: u32) -> (f64, f64) {
+        let scale = BASE_SPAN / self.zoom / self.width as f64;
+        let re = self.center.0 + (x as f64 + 0.5 - self.width as f64 / 2.0) * scale;
+        let im = self.center.1 + (y as f64 + 0.5 - self.height as f64 / 2.0) * scale;
+        (re, im)
+    }
+
+    fn max_iterations(&self) -> u32 {
+        BASE_ITERATIONS + ITERATIONS_PER_ZOOM * self.zoom.log2().max(0.0) as u32
+    }
+}
+
+/// Parses a complex number written as `re,im`, e.g. `-0.75,0.1`
+pub fn parse_point(s: &str) -> Result<(f64, f64), String> {
+    let invalid = || format!("Expected <x>,<y>: {}", s);
+    let (re, im) = s.split_once(',').ok_or_else(invalid)?;
+    match (re.trim().parse::<f64>(), im.trim().parse::<f64>()) {
+        (Ok(re), Ok(im)) => Ok((re, im)),
+        _ => Err(invalid()),
+    }
+}
+
+/* Iterates z -> z² + c from `z` and returns the smoothed escape count, or None if it never
+ * escapes */
+fn escape_time(mut z: (f64, f64), c: (f64, f64), max_iterations: u32) -> Option<f64> {
+    for n in 0..max_iterations {
+        let (re2, im2) = (z.0 * z.0, z.1 * z.1);
+        /* A larger radius than 2 keeps the smoothing below free of banding */
+        if re2 + im2 > 256.0 {
+            let log_modulus = (re2 + im2).ln() / 2.0;
+            return Some(n as f64 + 1.0 - (log_modulus / std::f64::consts::LN_2).log2());
+        }
+        z = (re2 - im2 + c.0, 2.0 * z.0 * z.1 + c.1);
+    }
+    None
+}
+
+/* Points inside the set are black, the rest cycle through the hues by how quickly they escape */
+fn color(escape: Option<f64>) -> Rgb<u8> {
+    match escape {
+        Some(n) => {
+            let (r, g, b) = hsv_to_rgb((n * HUE_STEP) as f32, 0.8, 1.0);
+            Rgb([r, g, b])
+        }
+        None => Rgb([0, 0, 0]),
+    }
+}
+
+/// Draws the fractal of `view`, shading every pixel by how quickly it escapes
+pub fn render(view: &FractalView) -> DynamicImage {
+    let max_iterations = view.max_iterations();
+    let img = RgbImage::from_fn(view.width, view.height, |x, y| {
+        let point = view.point_at(x, y);
+        color(match view.kind {
+            FractalKind::Mandelbrot => escape_time((0.0, 0.0), point, max_iterations),
+            FractalKind::Julia { c } => escape_time(point, c, max_iterations),
+        })
+    });
+    DynamicImage::ImageRgb8(img)
+}
+
+/// Renders `view` on another thread, the image is sent along with the view it shows once it's
+/// done
+pub fn render_in_background(view: FractalView) -> Receiver<(FractalView, DynamicImage)> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        /* Nobody is waiting anymore if the receiver was dropped, which is fine */
+        let _ = tx.send((view, render(&view)));
+    });
+    rx
+}
@@ -0,0 +1,72 @@
+/* Lines every few image pixels with their coordinates along the top and left edge */
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+use crate::image::Image;
+
+/* Zoomed out, lines closer than this many cells are thinned out by doubling the spacing */
+const MIN_CELLS: u32 = 6;
+
+pub struct Grid<'a> {
+    pub image: &'a Image,
+    /* In image pixels */
+    pub spacing: u32,
+}
+
+/* Image coordinates from `start` to `start + len` that are multiples of a spacing, at least
+ * `MIN_CELLS` apart on screen, paired with the cell they fall into */
+fn lines(start: u32, len: u32, cells: u16, origin: u16, spacing: u32) -> Vec<(u32, u16)> {
+    let mut spacing = spacing.max(1);
+    while (spacing as u64 * cells as u64) < (MIN_CELLS as u64 * len as u64) {
+        spacing *= 2;
+    }
+    (start.div_ceil(spacing) * spacing..start + len)
+        .step_by(spacing as usize)
+        .map(|v| {
+            let cell = ((v - start) as u64 * cells as u64 / len as u64) as u16;
+            (v, origin + cell)
+        })
+        .collect()
+}
+
+impl Widget for Grid<'_> {
+    /* Draws over the cells the image covered last, `area` is not used */
+    fn render(self, _area: Rect, buf: &mut Buffer) {
+        let drawn = self.image.drawn();
+        let (x0, y0, w, h) = self.image.visible_region();
+        if drawn.is_empty() || w == 0 || h == 0 {
+            return;
+        }
+        let columns = lines(x0, w, drawn.width, drawn.x, self.spacing);
+        let rows = lines(y0, h, drawn.height, drawn.y, self.spacing);
+
+        /* Only the foreground changes, so the bottom pixel of each cell still shows through */
+        let line = Style::new().fg(Color::DarkGray);
+        for &(_, x) in &columns {
+            for y in drawn.top()..drawn.bottom() {
+                buf[(x, y)].set_char('│').set_style(line);
+            }
+        }
+        for &(_, y) in &rows {
+            for x in drawn.left()..drawn.right() {
+                let cell = &mut buf[(x, y)];
+                let cross = columns.iter().any(|&(_, cx)| cx == x);
+                cell.set_char(if cross { '+' } else { '─' }).set_style(line);
+            }
+        }
+
+        let label = Style::new().fg(Color::Gray);
+        for &(v, x) in &columns {
+            let width = drawn.right().saturating_sub(x);
+            buf.set_stringn(x, drawn.y, v.to_string(), width as usize, label);
+        }
+        /* The top row already has the column labels */
+        for &(v, y) in rows.iter().filter(|&&(_, y)| y != drawn.y) {
+            buf.set_stringn(drawn.x, y, v.to_string(), drawn.width as usize, label);
+        }
+    }
+}
@@ -0,0 +1,39 @@
+/* HEIC/HEIF decoding through libheif, the image crate can't read these itself */
+use std::path::Path;
+
+use image::{
+    DynamicImage, ImageError, ImageResult, RgbaImage,
+    error::{DecodingError, ImageFormatHint},
+};
+use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+fn decoding_error(err: libheif_rs::HeifError) -> ImageError {
+    ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Name("HEIF".to_string()),
+        err,
+    ))
+}
+
+/// Decodes the primary image of the HEIF file at `path`, rotation and cropping stored in the
+/// file are already applied
+pub fn decode(path: &Path) -> ImageResult<DynamicImage> {
+    let ctx = HeifContext::read_from_file(&path.to_string_lossy()).map_err(decoding_error)?;
+    let handle = ctx.primary_image_handle().map_err(decoding_error)?;
+    let heif = LibHeif::new()
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+        .map_err(decoding_error)?;
+    let plane = heif
+        .planes()
+        .interleaved
+        .expect("interleaved RGBA has a single plane");
+
+    /* Rows may be padded, copy them one by one */
+    let row = plane.width as usize * 4;
+    let mut pixels = Vec::with_capacity(row * plane.height as usize);
+    for y in 0..plane.height as usize {
+        pixels.extend_from_slice(&plane.data[y * plane.stride..y * plane.stride + row]);
+    }
+    Ok(DynamicImage::ImageRgba8(
+        RgbaImage::from_raw(plane.width, plane.height, pixels).expect("buffer size matches"),
+    ))
+}
@@ -0,0 +1,96 @@
+use image::{DynamicImage, GenericImageView};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Position, Rect},
+    style::Color,
+    widgets::Widget,
+};
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const CHANNEL_COLORS: [Color; 3] = [Color::Red, Color::Green, Color::Blue];
+
+#[derive(Debug)]
+pub struct Histogram {
+    counts: [[u32; 256]; 3],
+}
+
+impl Histogram {
+    pub fn new(img: &DynamicImage) -> Self {
+        Self {
+            counts: Self::compute(img),
+        }
+    }
+
+    /// Rows taken up at the bottom of `area` when drawn into it
+    pub fn height(area: Rect) -> u16 {
+        (area.height / 6).min(32) * 3
+    }
+
+    /// Counts how often every value occurs in each of the R, G and B channels
+    pub fn compute(img: &DynamicImage) -> [[u32; 256]; 3] {
+        let mut counts = [[0; 256]; 3];
+        for (_, _, pixel) in img.pixels() {
+            for (channel, count) in counts.iter_mut().enumerate() {
+                count[pixel[channel] as usize] += 1;
+            }
+        }
+        counts
+    }
+}
+
+impl Widget for &Histogram {
+    /* Draws the three channels stacked in the bottom-right corner of `area`. Only the bars are
+     * written, so the image stays visible between them */
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let width = (area.width / 2).min(256);
+        let channel_h = Histogram::height(area) / 3;
+        if width == 0 || channel_h == 0 {
+            return;
+        }
+        let x0 = area.right() - width;
+        let y0 = area.bottom() - channel_h * 3;
+
+        for (channel, counts) in self.counts.iter().enumerate() {
+            /* Merge the 256 buckets into one column per cell */
+            let columns: Vec<u32> = (0..width as usize)
+                .map(|col| {
+                    let start = col * 256 / width as usize;
+                    let end = ((col + 1) * 256 / width as usize).max(start + 1);
+                    counts[start..end].iter().copied().max().unwrap_or(0)
+                })
+                .collect();
+            let max = columns.iter().copied().max().unwrap_or(0).max(1);
+            let bottom = y0 + channel_h * (channel as u16 + 1) - 1;
+
+            for (col, &count) in columns.iter().enumerate() {
+                /* Height in eighths of a cell */
+                let mut eighths = (count as u64 * channel_h as u64 * 8 / max as u64) as u16;
+                let mut y = bottom;
+                while eighths > 0 {
+                    let bar = BARS[(eighths.min(8) - 1) as usize];
+                    buf[Position::new(x0 + col as u16, y)]
+                        .set_char(bar)
+                        .set_fg(CHANNEL_COLORS[channel]);
+                    eighths = eighths.saturating_sub(8);
+                    y = y.saturating_sub(1);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn solid_color_fills_one_bucket_per_channel() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 3, Rgb([10, 128, 255])));
+        let counts = Histogram::compute(&img);
+        for (channel, value) in [10, 128, 255].into_iter().enumerate() {
+            assert_eq!(counts[channel][value], 12);
+            assert_eq!(counts[channel].iter().sum::<u32>(), 12);
+        }
+    }
+}
@@ -0,0 +1,32 @@
+/* Converting images with an embedded ICC profile to sRGB with qcms. Wide gamut photos, e.g. in
+ * Adobe RGB or ProPhoto RGB, look dull or wrong when their values are shown as sRGB */
+use std::path::Path;
+
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use qcms::{DataType, Intent, Profile, Transform};
+
+/// The ICC profile embedded in the image at `path`, if it has one
+pub fn read_profile(path: &Path) -> Option<Vec<u8>> {
+    let mut decoder = ImageReader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?
+        .into_decoder()
+        .ok()?;
+    decoder.icc_profile().ok()?
+}
+
+/// A transform from `profile` to sRGB, None if qcms can't make sense of the profile
+pub fn to_srgb(profile: &[u8]) -> Option<Transform> {
+    let input = Profile::new_from_slice(profile, false)?;
+    let mut srgb = Profile::new_sRGB();
+    srgb.precache_output_transform();
+    Transform::new(&input, &srgb, DataType::RGBA8, Intent::Perceptual)
+}
+
+/// `img` with the colors converted by `transform`, alpha is left alone
+pub fn apply(transform: &Transform, img: &DynamicImage) -> DynamicImage {
+    let mut rgba = img.to_rgba8();
+    transform.apply(&mut rgba);
+    DynamicImage::ImageRgba8(rgba)
+}
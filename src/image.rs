@@ -1,39 +1,814 @@
-use image::{DynamicImage, GenericImageView, ImageReader};
-use ratatui::{
-    buffer::Buffer,
-    layout::{Position, Rect},
-    style::Color,
-    widgets::Widget,
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
 };
 
-#[derive(Debug, Default)]
+use charcoal_core::{AlphaBackground, ColorDepth, RenderOptions, Renderer, render_to_buffer};
+use image::{DynamicImage, GenericImageView, imageops::FilterType};
+
+#[cfg(feature = "icc")]
+use crate::icc;
+use crate::{
+    animation::{self, Frames},
+    exif,
+    filters::{
+        Channel, EdgeAlgorithm, Filter, FilterPipeline, MirrorAxis, add_grain, detect_edges,
+        glitch, isolate_channel, mirror, vibrance, vignette,
+    },
+    fractal::FractalView,
+    life::Life,
+    pages,
+};
+use ratatui::{buffer::Buffer, layout::Rect, style::Color, widgets::Widget};
+
+/* Rotation (clockwise, in degrees) and flips, applied before zoom and pan */
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ImageTransform {
+    pub rotation: u16,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+}
+
+impl ImageTransform {
+    pub fn is_identity(&self) -> bool {
+        *self == ImageTransform::default()
+    }
+
+    pub fn rotate_cw(&mut self) {
+        self.rotation = (self.rotation + 90) % 360;
+    }
+
+    pub fn rotate_ccw(&mut self) {
+        self.rotation = (self.rotation + 270) % 360;
+    }
+
+    pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
+        let mut out = match self.rotation {
+            90 => img.rotate90(),
+            180 => img.rotate180(),
+            270 => img.rotate270(),
+            _ => img.clone(),
+        };
+        if self.flip_horizontal {
+            out = out.fliph();
+        }
+        if self.flip_vertical {
+            out = out.flipv();
+        }
+        out
+    }
+}
+
+/* What is done with the metadata of a file when it's opened, and again when it's reloaded */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OpenOptions {
+    /// Never read the EXIF data
+    pub strip_exif: bool,
+    /// Convert the colors from an embedded ICC profile to sRGB (with the icc feature)
+    pub icc_convert: bool,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        OpenOptions {
+            strip_exif: false,
+            icc_convert: true,
+        }
+    }
+}
+
+/* Everything about how the image is looked at, but not the pixels themselves, including the
+effects that are only drawn on top like edges, a single channel or the vignette */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewState {
+    pub zoom: f32,
+    pub pan_x: i32,
+    pub pan_y: i32,
+    pub transform: ImageTransform,
+    pub edges: Option<EdgeAlgorithm>,
+    pub channel: Option<Channel>,
+    pub vignette: f32,
+}
+
+impl Default for ViewState {
+    fn default() -> Self {
+        ViewState {
+            zoom: 1.0,
+            pan_x: 0,
+            pan_y: 0,
+            transform: ImageTransform::default(),
+            edges: None,
+            channel: None,
+            vignette: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct Image {
     pub path: String,
     pub image: DynamicImage,
     pub zoom: f32,
     pub pan_x: i32,
     pub pan_y: i32,
+    pub transform: ImageTransform,
+    pub renderer: Renderer,
+    pub color_depth: ColorDepth,
+    /* What transparent pixels are blended onto, `None` ignores the alpha channel */
+    pub alpha_background: Option<AlphaBackground>,
+
+    /* Transformed copy of `image` with the display adjustments below, keyed by the transform it
+     * was computed with */
+    transformed: Option<(ImageTransform, DynamicImage)>,
+    /* Previous versions of `image`, so destructive edits can be undone */
+    undo_stack: Vec<DynamicImage>,
+    /* The filters behind `image`, oldest first. Animations run them on every frame they show */
+    applied: Vec<Filter>,
+    /* Frames with `applied` run over them, filled in as they are shown */
+    filtered_frames: Vec<Option<DynamicImage>>,
+    /* Earlier and undone view states, separate from the filter undo above */
+    view_history: VecDeque<ViewState>,
+    view_redo: Vec<ViewState>,
+
+    /* Every frame of an animation and how long it is shown, empty for still images */
+    pub frames: Frames,
+    pub current: usize,
+    frame_shown: Option<Instant>,
+    /* First and last frame played, the animation loops between them. All frames for None */
+    frame_range: Option<(usize, usize)>,
+    /* Number of frames averaged together for a motion blur, 1 turns it off */
+    pub frame_blend: usize,
+    recent_frames: VecDeque<DynamicImage>,
+    /* Set for fractals, which are rendered instead of read from `path` */
+    pub fractal: Option<FractalView>,
+    /* Set for the Game of Life, whose image is redrawn every generation */
+    pub life: Option<Life>,
+    /* Where the photo was taken according to its EXIF data, as latitude and longitude */
+    pub gps: Option<(f64, f64)>,
+    /* The EXIF orientation tag, None if there is none */
+    pub orientation: Option<u32>,
+    options: OpenOptions,
+    /* False for tabs that weren't decoded yet, see `unloaded` */
+    loaded: bool,
+    /* Whether the colors were converted from the file's ICC profile */
+    pub icc_converted: bool,
+    /* Every page of a multi-page document, empty for everything else */
+    pub pages: Vec<DynamicImage>,
+    pub page: usize,
+    /* Before/after comparison: the part right of `split_pos` (0.0..=1.0 of the width) shows the
+     * displayed image with `split_filter` applied, which is cached in `split_filtered` */
+    split_filter: Option<Filter>,
+    split_pos: f32,
+    split_filtered: Option<DynamicImage>,
+    /* Filters run before the display adjustments below on every refresh */
+    pipeline: FilterPipeline,
+    /* Reference image shown on top at half opacity */
+    onion: Option<DynamicImage>,
+    /* -1.0..=1.0, 0 leaves the colors alone */
+    vibrance: f32,
+    /* Standard deviation of the film grain, 0 turns it off */
+    grain: f32,
+    /* Chance of each row being shifted, 0 turns it off */
+    glitch: f32,
+    /* Reflects one half of the screen onto the other */
+    mirror: Option<MirrorAxis>,
+    /* Only the edges are shown while this is set, `last_edges` is what toggling turns back on */
+    edges: Option<EdgeAlgorithm>,
+    last_edges: EdgeAlgorithm,
+    /* Show the edges in the colors of the image instead of white */
+    edge_colors: bool,
+    /* 0.0..=1.0, how dark the corners get. 0 turns it off */
+    vignette: f32,
+    /* Only this channel is shown, after the filter pipeline */
+    channel: Option<Channel>,
+    /* Where the image ended up on screen, for overlays drawn on top of it */
+    drawn: Rect,
+    /* Changes whenever `displayed()` might have, unique across all images */
+    generation: u64,
+}
+
+/* Generations are handed out from one counter, so two images never share one */
+static GENERATION: AtomicU64 = AtomicU64::new(1);
+
+fn next_generation() -> u64 {
+    GENERATION.fetch_add(1, Ordering::Relaxed)
 }
 
+/* Every undo step holds a full copy of the image, so keep only a few */
+const MAX_UNDO: usize = 10;
+/* View states are tiny, they can be kept for much longer */
+const MAX_VIEW_HISTORY: usize = 50;
+
 impl Image {
-    pub fn new(image_path: String) -> Self {
+    /// Opens the image at `image_path`, `options` also apply when it's reloaded
+    pub fn open(image_path: String, options: OpenOptions) -> image::ImageResult<Self> {
+        if let Some(pages) = pages::decode(Path::new(&image_path))? {
+            let mut image = Self::from_dynamic(image_path, pages[0].clone());
+            image.pages = pages;
+            image.options = options;
+            return Ok(image);
+        }
+        let (image, frames) = animation::decode(Path::new(&image_path))?;
+        let mut image = Self::from_dynamic(image_path, image);
+        image.frames = frames;
+        image.options = options;
+        image.convert_icc();
+        image.read_exif();
+        Ok(image)
+    }
+
+    /* Converts the image and every frame to sRGB if the file has an ICC profile. Pages of
+     * documents are left alone */
+    fn convert_icc(&mut self) {
+        self.icc_converted = false;
+        #[cfg(feature = "icc")]
+        if self.options.icc_convert
+            && let Some(transform) =
+                icc::read_profile(Path::new(&self.path)).and_then(|profile| icc::to_srgb(&profile))
+        {
+            self.image = icc::apply(&transform, &self.image);
+            for (frame, _) in &mut self.frames {
+                *frame = icc::apply(&transform, frame);
+            }
+            self.icc_converted = true;
+        }
+    }
+
+    fn read_exif(&mut self) {
+        if self.options.strip_exif {
+            return;
+        }
+        let exif = exif::read(Path::new(&self.path)).ok();
+        self.gps = exif.as_ref().and_then(exif::gps);
+        self.orientation = exif.as_ref().and_then(exif::orientation);
+    }
+
+    /// The pixels turned the way the EXIF orientation says, for saving them without the tag
+    pub fn upright(&self) -> Cow<'_, DynamicImage> {
+        match self.orientation {
+            Some(orientation @ 2..) => {
+                Cow::Owned(exif::apply_orientation(&self.image, orientation))
+            }
+            _ => Cow::Borrowed(&self.image),
+        }
+    }
+
+    /// A tab for the file at `path` that is only decoded by `load`, so opening a directory of
+    /// thousands of images doesn't keep all of them in memory
+    pub fn unloaded(path: String, options: OpenOptions) -> Self {
+        let mut image = Self::from_dynamic(path, DynamicImage::default());
+        image.options = options;
+        image.loaded = false;
+        image
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.loaded
+    }
+
+    /// Decodes an image made with `unloaded`. It counts as loaded even if that fails, so a
+    /// broken file isn't read again on every redraw
+    pub fn load(&mut self) -> image::ImageResult<()> {
+        self.loaded = true;
+        self.reload()?;
+        self.alpha_background = self
+            .image
+            .color()
+            .has_alpha()
+            .then_some(AlphaBackground::default());
+        Ok(())
+    }
+
+    /* For images that don't come from a file, `path` is only shown in the status bar */
+    pub fn from_dynamic(path: String, image: DynamicImage) -> Self {
+        /* Opaque images look the same either way, only transparent ones get a checkerboard */
+        let alpha_background = image
+            .color()
+            .has_alpha()
+            .then_some(AlphaBackground::default());
         Image {
-            image: ImageReader::open(image_path.clone())
-                .unwrap()
-                .decode()
-                .unwrap(),
-            path: image_path,
+            image,
+            path,
             zoom: 1.0,
             pan_x: 0,
             pan_y: 0,
+            transform: ImageTransform::default(),
+            renderer: Renderer::default(),
+            color_depth: ColorDepth::default(),
+            alpha_background,
+            transformed: None,
+            undo_stack: Vec::new(),
+            applied: Vec::new(),
+            filtered_frames: Vec::new(),
+            view_history: VecDeque::new(),
+            view_redo: Vec::new(),
+            frames: Vec::new(),
+            current: 0,
+            frame_shown: None,
+            frame_range: None,
+            frame_blend: 1,
+            recent_frames: VecDeque::new(),
+            fractal: None,
+            life: None,
+            gps: None,
+            orientation: None,
+            options: OpenOptions::default(),
+            loaded: true,
+            icc_converted: false,
+            pages: Vec::new(),
+            page: 0,
+            split_filter: None,
+            split_pos: 0.5,
+            split_filtered: None,
+            pipeline: FilterPipeline::default(),
+            onion: None,
+            vibrance: 0.0,
+            grain: 0.0,
+            glitch: 0.0,
+            mirror: None,
+            edges: None,
+            last_edges: EdgeAlgorithm::default(),
+            edge_colors: false,
+            vignette: 0.0,
+            channel: None,
+            drawn: Rect::default(),
+            generation: next_generation(),
+        }
+    }
+
+    /// Decodes the file from disk again, keeping zoom, pan and transform. Filters applied before,
+    /// like the ones from the command line, are run again on the new pixels
+    pub fn reload(&mut self) -> image::ImageResult<()> {
+        if let Some(pages) = pages::decode(Path::new(&self.path))? {
+            /* Stay on the same page unless the document got shorter */
+            self.page = self.page.min(pages.len() - 1);
+            self.image = pages[self.page].clone();
+            self.pages = pages;
+            self.frames.clear();
+        } else {
+            (self.image, self.frames) = animation::decode(Path::new(&self.path))?;
+            self.pages.clear();
+            self.page = 0;
+            self.convert_icc();
+        }
+        /* Keep playing the same frames unless the animation got shorter */
+        self.frame_range = self.frame_range.filter(|&(_, end)| end < self.frames.len());
+        self.current = self.frame_range.map_or(0, |(start, _)| start);
+        self.undo_stack.clear();
+        self.filtered_frames.clear();
+        if let Some((frame, _)) = self.frames.get(self.current) {
+            self.image = frame.clone();
+        }
+        for filter in std::mem::take(&mut self.applied) {
+            self.apply_filter(&filter);
         }
+        self.frame_shown = None;
+        self.recent_frames.clear();
+        self.changed();
+        self.read_exif();
+        Ok(())
+    }
+
+    /// Swaps in a newly generated version of the image, like a fractal that finished rendering
+    pub fn replace_image(&mut self, image: DynamicImage) {
+        self.image = image;
+        self.changed();
+        self.undo_stack.clear();
+        self.applied.clear();
+    }
+
+    pub fn apply_filter(&mut self, filter: &Filter) {
+        let filtered = filter.apply(&self.image);
+        if self.undo_stack.len() == MAX_UNDO {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack
+            .push(std::mem::replace(&mut self.image, filtered));
+        self.applied.push(filter.clone());
+        self.filtered_frames.clear();
+        self.changed();
+    }
+
+    /* Frame `index` of the animation with the applied filters, filtered once and then kept */
+    fn frame(&mut self, index: usize) -> DynamicImage {
+        if self.applied.is_empty() {
+            return self.frames[index].0.clone();
+        }
+        self.filtered_frames.resize(self.frames.len(), None);
+        let applied = &self.applied;
+        let raw = &self.frames[index].0;
+        self.filtered_frames[index]
+            .get_or_insert_with(|| {
+                applied
+                    .iter()
+                    .fold(raw.clone(), |image, filter| filter.apply(&image))
+            })
+            .clone()
+    }
+
+    /// Restores the image from before the last filter, returns false if there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(previous) => {
+                self.image = previous;
+                self.applied.pop();
+                self.filtered_frames.clear();
+                /* The frame from before the filter is stale once the animation moved on */
+                if !self.frames.is_empty() {
+                    self.image = self.frame(self.current);
+                }
+                self.changed();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves on to the next frame once the current one was shown long enough, returns true if
+    /// the frame changed
+    pub fn tick(&mut self, now: Instant) -> bool {
+        if self.frames.len() < 2 {
+            return false;
+        }
+        let shown = *self.frame_shown.get_or_insert(now);
+        if now.duration_since(shown) < self.frames[self.current].1 {
+            return false;
+        }
+        self.current = match self.frame_range {
+            Some((start, end)) if self.current >= end => start,
+            _ => (self.current + 1) % self.frames.len(),
+        };
+        self.frame_shown = Some(now);
+
+        let frame = self.frame(self.current);
+        self.image = if self.frame_blend > 1 {
+            self.recent_frames.push_back(frame);
+            while self.recent_frames.len() > self.frame_blend {
+                self.recent_frames.pop_front();
+            }
+            animation::blend(&self.recent_frames)
+        } else {
+            frame
+        };
+        self.changed();
+        true
+    }
+
+    /// Frame `index` of the animation the way it would be shown, with the filter pipeline,
+    /// display adjustments and transform applied
+    pub fn render_frame(&mut self, index: usize) -> DynamicImage {
+        let shown = (self.current, self.image.clone());
+        (self.current, self.image) = (index, self.frame(index));
+        self.changed();
+        self.refresh_transformed();
+        let frame = self.displayed().clone();
+        (self.current, self.image) = shown;
+        self.changed();
+        frame
+    }
+
+    /// Plays only frames `start` to `end` (counting from 0, the last frame if None), starting
+    /// with `start`
+    pub fn set_frame_range(&mut self, start: usize, end: Option<usize>) -> Result<(), String> {
+        let count = self.frames.len();
+        if count < 2 {
+            return Err(format!("{} is not an animation", self.path));
+        }
+        let end = end.unwrap_or(count - 1);
+        if start > end {
+            return Err(format!(
+                "End frame {} comes before start frame {}, playing the whole animation",
+                end, start
+            ));
+        }
+        if end >= count {
+            return Err(format!(
+                "Frames {} to {} are out of range, {} has frames 0 to {}",
+                start,
+                end,
+                self.path,
+                count - 1
+            ));
+        }
+        self.frame_range = Some((start, end));
+        self.seek(start);
+        Ok(())
+    }
+
+    /// Shows frame `frame` (counting from 0) of an animation and plays on from there, returns
+    /// false if there is no such frame
+    pub fn seek(&mut self, frame: usize) -> bool {
+        if frame >= self.frames.len() {
+            return false;
+        }
+        self.image = self.frame(frame);
+        self.current = frame;
+        self.frame_shown = None;
+        self.recent_frames.clear();
+        self.changed();
+        true
+    }
+
+    /// Shows page `page` (counting from 0) of a multi-page document, returns false if there is
+    /// no such page. Filters applied to the previous page can't be undone afterwards
+    pub fn set_page(&mut self, page: usize) -> bool {
+        let Some(image) = self.pages.get(page) else {
+            return false;
+        };
+        self.page = page;
+        self.image = image.clone();
+        self.undo_stack.clear();
+        self.applied.clear();
+        self.changed();
+        true
+    }
+
+    /// How long until `tick` will show the next frame, None for still images
+    pub fn until_next_frame(&self, now: Instant) -> Option<Duration> {
+        if self.frames.len() < 2 {
+            return None;
+        }
+        let shown = self.frame_shown.unwrap_or(now);
+        Some(
+            self.frames[self.current]
+                .1
+                .saturating_sub(now.duration_since(shown)),
+        )
+    }
+
+    pub fn view(&self) -> ViewState {
+        ViewState {
+            zoom: self.zoom,
+            pan_x: self.pan_x,
+            pan_y: self.pan_y,
+            transform: self.transform,
+            edges: self.edges,
+            channel: self.channel,
+            vignette: self.vignette,
+        }
+    }
+
+    fn set_view(&mut self, view: ViewState) {
+        self.zoom = view.zoom;
+        self.pan_x = view.pan_x;
+        self.pan_y = view.pan_y;
+        self.transform = view.transform;
+        self.set_edges(view.edges);
+        self.set_channel(view.channel);
+        self.set_vignette(view.vignette);
     }
-    pub fn render_image(&self, area: Rect, buf: &mut Buffer) {
-        let (img_w, img_h) = self.image.dimensions();
-        let term_cells_w = area.width as u32;
-        let term_cells_h = area.height as u32;
-        let term_pix_w = term_cells_w;
-        let term_pix_h = term_cells_h * 2;
+
+    /// Remembers the current view, call this before changing zoom, pan, transform or effects
+    pub fn save_view(&mut self) {
+        if self.view_history.len() == MAX_VIEW_HISTORY {
+            self.view_history.pop_front();
+        }
+        self.view_history.push_back(self.view());
+        self.view_redo.clear();
+    }
+
+    /// Goes back to the previous view, returns false if there is none
+    pub fn undo_view(&mut self) -> bool {
+        match self.view_history.pop_back() {
+            Some(previous) => {
+                self.view_redo.push(self.view());
+                self.set_view(previous);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reapplies the last undone view, returns false if there is none
+    pub fn redo_view(&mut self) -> bool {
+        match self.view_redo.pop() {
+            Some(next) => {
+                self.view_history.push_back(self.view());
+                self.set_view(next);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Goes to `view`, e.g. one saved earlier with `view()`. This can be undone like any other
+    /// view change
+    pub fn restore_view(&mut self, view: ViewState) {
+        self.save_view();
+        self.set_view(view);
+    }
+
+    /// Back to no zoom, pan, transform or effects, this can be undone like any other view change
+    pub fn reset_view(&mut self) {
+        self.save_view();
+        self.set_view(ViewState::default());
+    }
+
+    /// Shows `onion` blended over the image, or stops doing so for None
+    pub fn set_onion_skin(&mut self, onion: Option<DynamicImage>) {
+        self.onion = onion;
+        self.changed();
+    }
+
+    /// Boosts (or for negative values mutes) the less saturated colors on screen, the image
+    /// itself is left alone
+    pub fn set_vibrance(&mut self, vibrance: f32) {
+        self.vibrance = vibrance;
+        self.changed();
+    }
+
+    /// Shows the image with `filter` applied right of a movable line, None turns it off
+    pub fn set_split_view(&mut self, filter: Option<Filter>) {
+        self.split_filter = filter;
+        self.split_filtered = None;
+        self.changed();
+    }
+
+    /// Moves the line of the before/after comparison by `by` times the width of the image
+    pub fn move_split(&mut self, by: f32) {
+        self.split_pos = (self.split_pos + by).clamp(0.0, 1.0);
+    }
+
+    /// Adds `filter` to the end of the filters run on screen, returns how many there are now
+    pub fn add_pipeline_filter(&mut self, name: String, filter: Filter) -> usize {
+        self.pipeline.push(name, filter);
+        self.changed();
+        self.pipeline.len()
+    }
+
+    /// The filters run on screen, in order
+    pub fn pipeline(&self) -> &FilterPipeline {
+        &self.pipeline
+    }
+
+    /// Removes the filter at `index` from the ones run on screen, returns false if there is none
+    pub fn remove_pipeline_filter(&mut self, index: usize) -> bool {
+        self.changed();
+        self.pipeline.remove(index)
+    }
+
+    /// Moves a filter run on screen from `from` to `to`, returns false if either is out of range
+    pub fn move_pipeline_filter(&mut self, from: usize, to: usize) -> bool {
+        self.changed();
+        self.pipeline.move_filter(from, to)
+    }
+
+    /// Removes every filter run on screen, applied filters stay
+    pub fn clear_pipeline(&mut self) {
+        self.pipeline.clear();
+        self.changed();
+    }
+
+    /// Adds film grain on screen, 0 turns it off
+    pub fn set_grain(&mut self, strength: f32) {
+        self.grain = strength;
+        self.changed();
+    }
+
+    /// Darkens the corners on screen by `strength`, clamped to 0.0..=1.0. 0 turns it off
+    pub fn set_vignette(&mut self, strength: f32) {
+        self.vignette = strength.clamp(0.0, 1.0);
+        self.changed();
+    }
+
+    pub fn vignette(&self) -> f32 {
+        self.vignette
+    }
+
+    /// Shows only `channel` on screen, None shows the whole image again
+    pub fn set_channel(&mut self, channel: Option<Channel>) {
+        self.channel = channel;
+        self.changed();
+    }
+
+    pub fn channel(&self) -> Option<Channel> {
+        self.channel
+    }
+
+    /// Makes the image symmetric on screen along `axis`, None turns it off
+    pub fn set_mirror(&mut self, axis: Option<MirrorAxis>) {
+        self.mirror = axis;
+        self.changed();
+    }
+
+    /// Shows only the edges found by `algorithm`, None turns it off
+    pub fn set_edges(&mut self, algorithm: Option<EdgeAlgorithm>) {
+        if let Some(algorithm) = algorithm {
+            self.last_edges = algorithm;
+        }
+        self.edges = algorithm;
+        self.changed();
+    }
+
+    /// Turns the edge view on with the algorithm used last, or off again
+    pub fn toggle_edges(&mut self) {
+        let edges = match self.edges {
+            Some(_) => None,
+            None => Some(self.last_edges),
+        };
+        self.set_edges(edges);
+    }
+
+    pub fn set_edge_colors(&mut self, colored: bool) {
+        self.edge_colors = colored;
+        self.changed();
+    }
+
+    /// Shifts random rows on screen, each with the chance `probability`. 0 turns it off
+    pub fn set_glitch(&mut self, probability: f32) {
+        self.glitch = probability;
+        self.changed();
+    }
+
+    /* Recompute the transformed copy, but only if the transform changed since last time. The
+     * display adjustments are applied here too, so they are rotated and flipped along with the
+     * image */
+    fn refresh_transformed(&mut self) {
+        let adjusted = self.split_filter.is_some()
+            || !self.pipeline.is_empty()
+            || self.onion.is_some()
+            || self.vibrance != 0.0
+            || self.grain > 0.0
+            || self.glitch > 0.0
+            || self.mirror.is_some()
+            || self.edges.is_some()
+            || self.vignette > 0.0
+            || self.channel.is_some();
+        if self.transform.is_identity() && !adjusted {
+            if self.transformed.take().is_some() {
+                self.generation = next_generation();
+            }
+        } else if !matches!(&self.transformed, Some((t, _)) if *t == self.transform) {
+            self.generation = next_generation();
+            let mut base = Cow::Borrowed(&self.image);
+            if !self.pipeline.is_empty() {
+                base = Cow::Owned(self.pipeline.apply(&base));
+            }
+            if let Some(channel) = self.channel {
+                base = Cow::Owned(isolate_channel(&base, channel));
+            }
+            if self.vibrance != 0.0 {
+                base = Cow::Owned(vibrance(&base, self.vibrance));
+            }
+            if let Some(onion) = &self.onion {
+                let (w, h) = base.dimensions();
+                let onion = onion.resize_exact(w, h, FilterType::Triangle);
+                base = Cow::Owned(animation::blend(&VecDeque::from([
+                    base.into_owned(),
+                    onion,
+                ])));
+            }
+            if self.grain > 0.0 {
+                /* Seeded by the frame, so still images keep their grain and animations don't */
+                add_grain(base.to_mut(), self.grain, self.current as u64);
+            }
+            if self.glitch > 0.0 {
+                glitch(base.to_mut(), self.glitch, self.current as u64);
+            }
+            if let Some(algorithm) = self.edges {
+                base = Cow::Owned(detect_edges(&base, algorithm, self.edge_colors));
+            }
+            let mut transformed = self.transform.apply(&base);
+            /* After the transform, so the symmetry is along the screen and not the file */
+            if let Some(axis) = self.mirror {
+                transformed = mirror(&transformed, axis);
+            }
+            /* Last, so the corners of the screen are darkened whatever happened before */
+            if self.vignette > 0.0 {
+                transformed = vignette(&transformed, self.vignette);
+            }
+            self.split_filtered = self.split_filter.as_ref().map(|f| f.apply(&transformed));
+            self.transformed = Some((self.transform, transformed));
+        }
+    }
+
+    /// Changes whenever the pixels of `displayed()` might have, so renderers that send whole
+    /// images can tell if they have to send them again
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /* Drops the transformed copy after something changed what is shown */
+    fn changed(&mut self) {
+        self.transformed = None;
+        self.generation = next_generation();
+    }
+
+    /// The image as it's shown on screen, with the transform applied
+    pub fn displayed(&self) -> &DynamicImage {
+        match &self.transformed {
+            Some((_, img)) => img,
+            None => &self.image,
+        }
+    }
+
+    /// The part of `displayed()` that is on screen at the current zoom and pan, as
+    /// `(x, y, width, height)` in pixels
+    pub fn visible_region(&self) -> (u32, u32, u32, u32) {
+        let (img_w, img_h) = self.displayed().dimensions();
 
         // 1) compute zoomed source‐rect in image coords
         let src_w = (img_w as f32 / self.zoom).round() as u32;
@@ -50,53 +825,113 @@ impl Image {
 
         let src_x0 = (base_x0 + self.pan_x).clamp(0, max_x0) as u32;
         let src_y0 = (base_y0 + self.pan_y).clamp(0, max_y0) as u32;
+        (src_x0, src_y0, src_w, src_h)
+    }
 
-        // 2) same uniform scale to fit that rect into terminal pix
-        let scale_x = term_pix_w as f32 / src_w as f32;
-        let scale_y = term_pix_h as f32 / src_h as f32;
-        let scale = scale_x.min(scale_y);
-
-        let scaled_w = (src_w as f32 * scale).round() as u32;
-        let scaled_h = (src_h as f32 * scale).round() as u32;
-        let scaled_cells_w = scaled_w;
-        let scaled_cells_h = (scaled_h + 1) / 2;
+    /// Zooms and pans so the `(x, y, width, height)` region of the image fills the view, as far
+    /// as the aspect ratio allows, and is centered in it
+    pub fn focus(&mut self, (x, y, width, height): (u32, u32, u32, u32)) {
+        let (img_w, img_h) = self.displayed().dimensions();
+        let (width, height) = (width.max(1), height.max(1));
+        self.zoom = (img_w as f32 / width as f32)
+            .min(img_h as f32 / height as f32)
+            .max(1.0);
+        self.center_on((
+            x as f32 + width as f32 / 2.0,
+            y as f32 + height as f32 / 2.0,
+        ));
+    }
 
-        // 3) center offsets
-        let offset_x = ((term_cells_w as i32 - scaled_cells_w as i32) / 2).max(0) as u32;
-        let offset_y = ((term_cells_h as i32 - scaled_cells_h as i32) / 2).max(0) as u32;
+    /* Pans so the point `(x, y)` of the image is in the middle of the view, or as close as the
+     * edges allow */
+    fn center_on(&mut self, (x, y): (f32, f32)) {
+        let (img_w, img_h) = self.displayed().dimensions();
+        let (_, _, w, h) = self.visible_region();
+        self.pan_x = (x - w as f32 / 2.0 - ((img_w - w) / 2) as f32).round() as i32;
+        self.pan_y = (y - h as f32 / 2.0 - ((img_h - h) / 2) as f32).round() as i32;
+    }
 
-        // clear letterbox
-        for y in 0..term_cells_h {
-            for x in 0..term_cells_w {
-                let pos = Position::new(area.x + x as u16, area.y + y as u16);
-                buf[pos].reset();
-            }
+    /// Pans to the luminance-weighted centroid of the image, so the brightest part ends up in
+    /// the middle of the view. Does nothing on an all-black image
+    pub fn center_on_mass(&mut self) {
+        let luma = self.displayed().to_luma8();
+        let (mut sum, mut sum_x, mut sum_y) = (0.0, 0.0, 0.0);
+        for (x, y, pixel) in luma.enumerate_pixels() {
+            let weight = pixel[0] as f64;
+            sum += weight;
+            sum_x += x as f64 * weight;
+            sum_y += y as f64 * weight;
         }
+        if sum > 0.0 {
+            self.center_on(((sum_x / sum) as f32, (sum_y / sum) as f32));
+        }
+    }
 
-        // 4) draw each cell sampling from the zoomed rect
-        for cell_y in 0..scaled_cells_h {
-            for cell_x in 0..scaled_cells_w {
-                let tx = offset_x + cell_x;
-                let ty = offset_y + cell_y;
-
-                let fx = (cell_x as f32 + 0.5) * src_w as f32 / scaled_w as f32;
-                let fy_top = (cell_y * 2) as f32 * src_h as f32 / scaled_h as f32;
-                let fy_bot = (cell_y * 2 + 1) as f32 * src_h as f32 / scaled_h as f32;
-
-                let img_x = src_x0 + fx.floor() as u32;
-                let top_y = src_y0 + fy_top.floor() as u32;
-                let bot_y = src_y0 + fy_bot.floor() as u32;
+    /// Multiplies the zoom by `factor`, keeping the pixel at `anchor` where it is on screen.
+    /// `anchor` is a fraction of the visible region, (0.5, 0.5) zooms around its center
+    pub fn zoom_around(&mut self, factor: f32, anchor: (f32, f32)) {
+        let (x0, y0, w, h) = self.visible_region();
+        let point = (
+            x0 as f32 + anchor.0 * w as f32,
+            y0 as f32 + anchor.1 * h as f32,
+        );
+        self.zoom *= factor;
+        /* Solve for the pan that puts `point` back at `anchor` in the new region */
+        let (img_w, img_h) = self.displayed().dimensions();
+        let (_, _, w, h) = self.visible_region();
+        self.pan_x = (point.0 - anchor.0 * w as f32 - ((img_w - w) / 2) as f32).round() as i32;
+        self.pan_y = (point.1 - anchor.1 * h as f32 - ((img_h - h) / 2) as f32).round() as i32;
+    }
 
-                let pixel_top = self.image.get_pixel(img_x, top_y.clamp(0, img_h - 1));
-                let pixel_bot = self.image.get_pixel(img_x, bot_y.clamp(0, img_h - 1));
+    /// Draws the visible region into `area` and returns the cells it covers
+    pub fn render_image(&self, area: Rect, buf: &mut Buffer) -> Rect {
+        let region = self.visible_region();
+        let drawn = self.render_region(area, buf, region);
+        let Some(filtered) = &self.split_filtered else {
+            return drawn;
+        };
 
-                let pos = Position::new(area.x + tx as u16, area.y + ty as u16);
-                let cell = &mut buf[pos];
-                cell.set_char('▀')
-                    .set_fg(Color::Rgb(pixel_top[0], pixel_top[1], pixel_top[2]))
-                    .set_bg(Color::Rgb(pixel_bot[0], pixel_bot[1], pixel_bot[2]));
+        /* Draw the filtered image the same way and copy over the part right of the line */
+        let mut after = Buffer::empty(area);
+        let opts = RenderOptions {
+            renderer: self.renderer,
+            color_depth: self.color_depth,
+            region: Some(region),
+            alpha_background: self.alpha_background,
+        };
+        render_to_buffer(filtered, area, &mut after, &opts);
+        let split_x = drawn.x + (drawn.width as f32 * self.split_pos).round() as u16;
+        for y in drawn.top()..drawn.bottom() {
+            for x in split_x..drawn.right() {
+                buf[(x, y)] = after[(x, y)].clone();
+            }
+            if split_x < drawn.right() {
+                buf[(split_x, y)].set_char('│').set_fg(Color::White);
             }
         }
+        drawn
+    }
+
+    /// The cells covered by the image the last time it was drawn
+    pub fn drawn(&self) -> Rect {
+        self.drawn
+    }
+
+    /// Draws the `(x, y, width, height)` part of `displayed()` centered in `area` and returns
+    /// the cells it covers
+    pub fn render_region(
+        &self,
+        area: Rect,
+        buf: &mut Buffer,
+        region: (u32, u32, u32, u32),
+    ) -> Rect {
+        let opts = RenderOptions {
+            renderer: self.renderer,
+            color_depth: self.color_depth,
+            region: Some(region),
+            alpha_background: self.alpha_background,
+        };
+        render_to_buffer(self.displayed(), area, buf, &opts)
     }
 }
 
@@ -116,6 +951,92 @@ impl Widget for &mut Image {
 
         /* Also, the program has to handle images and videos differently */
         // For now, only images will be handled
-        self.render_image(area, buf);
+        self.refresh_transformed();
+        self.drawn = self.render_image(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_apply_to_every_frame() {
+        let gray = |v| {
+            DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+                2,
+                2,
+                image::Rgba([v, v, v, 255]),
+            ))
+        };
+        let mut image = Image::from_dynamic("anim".to_string(), gray(0x80));
+        image.frames = vec![
+            (gray(0x80), Duration::from_millis(100)),
+            (gray(0x40), Duration::from_millis(100)),
+        ];
+        image.apply_filter(&Filter::parse(&["posterize", "2"]).unwrap());
+        assert!(image.seek(1));
+        assert_eq!(image.image.to_rgba8().get_pixel(0, 0)[0], 0);
+        assert!(image.seek(0));
+        assert_eq!(image.image.to_rgba8().get_pixel(0, 0)[0], 255);
+        assert!(image.undo());
+        assert_eq!(image.image.to_rgba8().get_pixel(0, 0)[0], 0x80);
+    }
+
+    #[test]
+    fn reload_keeps_the_filters() {
+        let path = std::env::temp_dir().join(format!("charcoal-reload-{}.png", std::process::id()));
+        let write = |v| image::RgbImage::from_pixel(2, 2, image::Rgb([v, v, v])).save(&path);
+        write(0x80).unwrap();
+        let mut image =
+            Image::open(path.to_string_lossy().into_owned(), OpenOptions::default()).unwrap();
+        image.apply_filter(&Filter::parse(&["posterize", "2"]).unwrap());
+        write(0x40).unwrap();
+        let reloaded = image.reload();
+        let _ = std::fs::remove_file(&path);
+        reloaded.unwrap();
+        assert_eq!(image.image.to_rgba8().get_pixel(0, 0)[0], 0);
+        assert!(image.undo());
+        assert_eq!(image.image.to_rgba8().get_pixel(0, 0)[0], 0x40);
+    }
+
+    #[test]
+    fn backwards_frame_range_plays_everything() {
+        let frame = DynamicImage::new_rgba8(1, 1);
+        let mut image = Image::from_dynamic("anim".to_string(), frame.clone());
+        image.frames = vec![(frame.clone(), Duration::from_millis(100)); 4];
+        assert!(image.set_frame_range(3, Some(1)).is_err());
+        assert_eq!(image.frame_range, None);
+        assert!(image.set_frame_range(1, Some(2)).is_ok());
+        assert_eq!(image.frame_range, Some((1, 2)));
+    }
+
+    #[test]
+    fn generation_follows_what_is_shown() {
+        let mut image = Image::from_dynamic("gray".to_string(), DynamicImage::new_rgba8(2, 2));
+        image.refresh_transformed();
+        let still = image.generation();
+        image.refresh_transformed();
+        assert_eq!(image.generation(), still);
+        image.transform.rotate_cw();
+        image.refresh_transformed();
+        let rotated = image.generation();
+        assert_ne!(rotated, still);
+        image.apply_filter(&Filter::parse(&["posterize", "2"]).unwrap());
+        assert_ne!(image.generation(), rotated);
+    }
+
+    #[test]
+    fn undo_brings_back_effects() {
+        let mut image = Image::default();
+        let before = image.view();
+        image.save_view();
+        image.set_edges(Some(EdgeAlgorithm::default()));
+        image.set_vignette(0.5);
+        assert!(image.undo_view());
+        assert_eq!(image.view(), before);
+        assert!(image.redo_view());
+        assert_eq!(image.view().edges, Some(EdgeAlgorithm::default()));
+        assert_eq!(image.view().vignette, 0.5);
     }
 }
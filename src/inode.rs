@@ -0,0 +1,80 @@
+/* Finding a file from its `device:inode` pair (as printed by `stat` or used by `find -inum`).
+ * Linux has no way to open a file by inode, so the device's mount point is looked up in
+ * /proc/self/mountinfo and walked until a file with that inode turns up */
+use std::{
+    fs,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{Result, eyre};
+
+/* Splits a `dev_t` into major and minor numbers, the same way glibc does */
+fn major_minor(dev: u64) -> (u64, u64) {
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    (major, minor)
+}
+
+fn mount_point(major: u64, minor: u64) -> Option<PathBuf> {
+    let mountinfo = fs::read_to_string("/proc/self/mountinfo").ok()?;
+    let wanted = format!("{}:{}", major, minor);
+    /* Fields: id parent major:minor root mount-point ... */
+    mountinfo.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let dev = fields.nth(2)?;
+        let mount = fields.nth(1)?;
+        (dev == wanted).then(|| PathBuf::from(mount.replace("\\040", " ")))
+    })
+}
+
+fn walk(root: &Path, dev: u64, ino: u64) -> Option<PathBuf> {
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            /* Don't wander into other filesystems mounted below this one */
+            if meta.dev() != dev {
+                continue;
+            }
+            if meta.ino() == ino {
+                return Some(entry.path());
+            }
+            if meta.is_dir() {
+                dirs.push(entry.path());
+            }
+        }
+    }
+    None
+}
+
+/// Resolves `major:minor:inode` or `major:minor` style specs like `8:1:1234567`, as well as the
+/// shorter `dev:ino` form where `dev` is the device number as a single integer (`stat -c %d`)
+pub fn find_by_inode(spec: &str) -> Result<PathBuf> {
+    let parts: Vec<u64> = spec
+        .split(':')
+        .map(|p| p.parse::<u64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| eyre!("Invalid inode spec: {}", spec))?;
+    let (major, minor, ino) = match parts.as_slice() {
+        [major, minor, ino] => (*major, *minor, *ino),
+        [dev, ino] => {
+            let (major, minor) = major_minor(*dev);
+            (major, minor, *ino)
+        }
+        _ => return Err(eyre!("Expected <dev:ino>, got {}", spec)),
+    };
+
+    let root = mount_point(major, minor)
+        .ok_or_else(|| eyre!("No mounted filesystem for device {}:{}", major, minor))?;
+    let dev = fs::metadata(&root)?.dev();
+    if root.metadata()?.ino() == ino {
+        return Ok(root);
+    }
+    walk(&root, dev, ino).ok_or_else(|| eyre!("No file with inode {} on {}", ino, root.display()))
+}
@@ -0,0 +1,132 @@
+/* Drawing images with the inline image protocol of iTerm2, which WezTerm supports as well. The
+ * image goes straight to the terminal as a PNG in an OSC 1337 sequence, ratatui only leaves
+ * the cells under it blank */
+use std::io::{self, Write};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use image::{DynamicImage, ImageEncoder, codecs::png::PngEncoder};
+use ratatui::{
+    crossterm::{
+        cursor::{MoveTo, RestorePosition, SavePosition},
+        execute,
+        terminal::window_size,
+    },
+    layout::Rect,
+};
+
+/* Guess for terminals that don't report their size in pixels */
+const DEFAULT_CELL_SIZE: (u32, u32) = (8, 16);
+
+/// What an image on screen is cut from, cheap to compare so unchanged images aren't cropped
+/// and encoded again on every frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Source {
+    /// `Image::generation` of the image
+    pub generation: u64,
+    /// `Image::visible_region` of the image
+    pub region: (u32, u32, u32, u32),
+}
+
+#[derive(Debug, Default)]
+struct Shown {
+    area: Rect,
+    /* None until something was sent, e.g. after `forget` */
+    source: Option<(Source, (u32, u32))>,
+    png: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+pub struct ITerm2Renderer {
+    /* What was sent last and where, so unchanged images aren't sent again on every frame */
+    shown: Vec<Shown>,
+}
+
+/* Pixels per cell, so the PNG is about as detailed as the terminal can show */
+fn cell_size() -> (u32, u32) {
+    match window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => (
+            (size.width / size.columns) as u32,
+            (size.height / size.rows) as u32,
+        ),
+        _ => DEFAULT_CELL_SIZE,
+    }
+}
+
+fn encode_png(img: &DynamicImage) -> io::Result<Vec<u8>> {
+    let rgba = img.to_rgba8();
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png)
+        .write_image(
+            &rgba,
+            rgba.width(),
+            rgba.height(),
+            image::ExtendedColorType::Rgba8,
+        )
+        .map_err(io::Error::other)?;
+    Ok(png)
+}
+
+impl ITerm2Renderer {
+    /// Whether `areas` are where the images were sent last time. If not, the screen has to be
+    /// cleared first so the old images don't stay around
+    pub fn same_areas(&self, areas: &[Rect]) -> bool {
+        self.shown.iter().map(|shown| &shown.area).eq(areas)
+    }
+
+    /// Treats the images at `areas` as unknown, so they are all sent on the next `draw`, e.g.
+    /// after the screen was cleared
+    pub fn forget(&mut self, areas: &[Rect]) {
+        self.shown = areas
+            .iter()
+            .map(|&area| Shown {
+                area,
+                ..Shown::default()
+            })
+            .collect();
+    }
+
+    /// Sends every image to fill its `(source, area)` on screen, `crop(i)` cuts out the pixels
+    /// of the i-th one. Images with the same source and area as last time are neither cropped
+    /// nor sent again, unless `force` is set, e.g. because a popup covered them
+    pub fn draw(
+        &mut self,
+        images: &[(Source, Rect)],
+        crop: impl Fn(usize) -> DynamicImage,
+        force: bool,
+    ) -> io::Result<()> {
+        let cells = cell_size();
+        let (cell_w, cell_h) = cells;
+        let mut stdout = io::stdout();
+        let mut previous = std::mem::take(&mut self.shown).into_iter();
+        for (i, &(source, area)) in images.iter().enumerate() {
+            let key = Some((source, cells));
+            let (unchanged, png) = match previous.next() {
+                Some(shown) if shown.area == area && shown.source == key => (true, shown.png),
+                _ => {
+                    let img = crop(i)
+                        .thumbnail_exact(area.width as u32 * cell_w, area.height as u32 * cell_h);
+                    (false, encode_png(&img)?)
+                }
+            };
+            if force || !unchanged {
+                execute!(stdout, SavePosition, MoveTo(area.x, area.y))?;
+                write!(
+                    stdout,
+                    "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=0:{}\x07",
+                    png.len(),
+                    area.width,
+                    area.height,
+                    STANDARD.encode(&png)
+                )?;
+                /* The terminal moves the cursor below the image, ratatui expects it back */
+                execute!(stdout, RestorePosition)?;
+            }
+            self.shown.push(Shown {
+                area,
+                source: key,
+                png,
+            });
+        }
+        Ok(())
+    }
+}
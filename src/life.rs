@@ -0,0 +1,82 @@
+/* Conway's Game of Life on a grid that wraps around at the edges */
+use image::{DynamicImage, GrayImage, Luma};
+use rand::RngExt;
+
+/* Share of the cells alive after randomizing, denser grids mostly die off in the first steps */
+const ALIVE_CHANCE: f64 = 0.3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Life {
+    width: u32,
+    height: u32,
+    cells: Vec<bool>,
+    pub generation: u64,
+    pub paused: bool,
+}
+
+impl Life {
+    /// A `width`x`height` grid with every cell randomly alive or dead
+    pub fn random(width: u32, height: u32) -> Self {
+        let mut life = Life {
+            width,
+            height,
+            cells: Vec::new(),
+            generation: 0,
+            paused: false,
+        };
+        life.randomize();
+        life
+    }
+
+    /// Starts over from generation 0 with new random cells
+    pub fn randomize(&mut self) {
+        let mut rng = rand::rng();
+        self.cells = (0..self.width * self.height)
+            .map(|_| rng.random_bool(ALIVE_CHANCE))
+            .collect();
+        self.generation = 0;
+    }
+
+    fn alive(&self, x: i64, y: i64) -> bool {
+        let x = x.rem_euclid(self.width as i64) as u32;
+        let y = y.rem_euclid(self.height as i64) as u32;
+        self.cells[(y * self.width + x) as usize]
+    }
+
+    /// Advances one generation. Live cells with two or three live neighbours survive, dead
+    /// cells with exactly three come alive
+    pub fn step(&mut self) {
+        let mut next = Vec::with_capacity(self.cells.len());
+        for y in 0..self.height as i64 {
+            for x in 0..self.width as i64 {
+                let neighbours = [-1, 0, 1]
+                    .iter()
+                    .flat_map(|dy| [-1, 0, 1].map(|dx| (dx, *dy)))
+                    .filter(|&(dx, dy)| (dx, dy) != (0, 0) && self.alive(x + dx, y + dy))
+                    .count();
+                next.push(matches!((self.alive(x, y), neighbours), (true, 2) | (_, 3)));
+            }
+        }
+        self.cells = next;
+        self.generation += 1;
+    }
+
+    /// One pixel per cell, white for live cells and black for dead ones
+    pub fn image(&self) -> DynamicImage {
+        let img = GrayImage::from_fn(self.width, self.height, |x, y| {
+            let alive = self.cells[(y * self.width + x) as usize];
+            Luma([if alive { 255 } else { 0 }])
+        });
+        DynamicImage::ImageLuma8(img)
+    }
+}
+
+/// Parses the size of a grid written as `WxH`, e.g. `120x80`
+pub fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("Expected <width>x<height>: {}", s);
+    let (width, height) = s.split_once('x').ok_or_else(invalid)?;
+    match (width.parse::<u32>(), height.parse::<u32>()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(invalid()),
+    }
+}
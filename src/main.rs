@@ -1,27 +1,126 @@
 use std::{
-    env::{self},
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{IsTerminal, Read},
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{
+        Arc,
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
     time::{Duration, Instant},
 };
 
+use charcoal_core::{
+    AlphaBackground, CropMode, DataFormat, JsonWriter, OutputFormat, Renderer, ascii, write_csv,
+};
 use color_eyre::eyre::{Ok, Result};
 
+mod animation;
+mod batch;
+mod capabilities;
+mod chessboard;
+mod cli;
+mod config;
+mod exif;
+mod filters;
+mod font_preview;
+mod fractal;
+mod grid;
+#[cfg(feature = "heif")]
+mod heif;
+mod histogram;
+#[cfg(feature = "icc")]
+mod icc;
 mod image;
+#[cfg(target_os = "linux")]
+mod inode;
+mod iterm2;
+mod life;
+mod minimap;
+mod noise;
+mod pages;
+mod palette;
+#[cfg(feature = "macos-photos")]
+mod photos;
+mod plasma;
+mod signals;
+mod stack;
+mod stats;
+#[cfg(feature = "svg")]
+mod svg;
+mod watch;
+mod watermark;
 
-use image::Image;
+use ::image::DynamicImage;
+use capabilities::{TerminalCapabilities, detect_capabilities};
+use chessboard::Chessboard;
+use cli::CliArgs;
+use config::Config;
+use filters::{Channel, EdgeAlgorithm, Filter, MirrorAxis, SortAxis, SortKey, TileLibrary};
+use fractal::{FractalKind, FractalView};
+use grid::Grid;
+use histogram::Histogram;
+use image::{Image, OpenOptions, ViewState};
+use iterm2::ITerm2Renderer;
+use life::Life;
+use minimap::Minimap;
+use noise::NoiseSettings;
+use palette::Palette;
 use ratatui::{
     DefaultTerminal,
     buffer::Buffer,
-    crossterm::event::{self, Event, KeyCode, KeyEvent},
+    crossterm::{
+        ExecutableCommand,
+        event::{
+            self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+            MouseButton, MouseEvent, MouseEventKind,
+        },
+    },
     layout::{
         Constraint::{self, *},
-        Direction, Layout, Rect,
+        Direction, Layout, Position, Rect,
     },
+    style::{Color, Style},
     text::Text,
-    widgets::Widget,
+    widgets::{Block, Clear, Row, Table, Widget},
 };
+use signals::Signals;
+use stats::{ImageStats, compute_stats};
+use watch::FileWatcher;
+use watermark::{Watermark, WatermarkPosition};
 
-struct CliArgs {
-    path: String,
+/* What the app works out from the command line, everything else is read from `CliArgs` as
+ * clap parsed it */
+#[derive(Debug, Default)]
+struct Computed {
+    /* The filters of every flag, in the order they run */
+    filters: Vec<Filter>,
+    /* Of --crop, --no-crop and --letterbox, the last one given */
+    crop: CropMode,
+    mirror: Option<MirrorAxis>,
+    glitch: Option<f32>,
+    alpha_background: Option<AlphaBackground>,
+    watermark: Option<(String, WatermarkPosition)>,
+    /* Settings given on the command line, they take precedence over the config file */
+    settings: Config,
+    /* Shown instead of an image file */
+    fractal: Option<FractalView>,
+    chessboard: Option<Chessboard>,
+    /* The width and height of the plasma animation */
+    plasma: Option<(u32, u32)>,
+    noise: Option<NoiseSettings>,
+}
+
+impl CliArgs {
+    fn open_options(&self) -> OpenOptions {
+        OpenOptions {
+            strip_exif: self.strip_exif,
+            icc_convert: !self.no_icc_convert,
+        }
+    }
 }
 
 /* Mode definitions for a vim-like interface */
@@ -30,43 +129,268 @@ enum Mode {
     #[default]
     Normal,
     Command,
+    /* The keybinding overlay, scrolled down by `scroll` rows */
+    Help {
+        scroll: usize,
+    },
+    /* Waiting for the letter after m, or after ' when `recall` is set */
+    Mark {
+        recall: bool,
+    },
+    /* Waiting for a rectangle to be dragged with the mouse, `start` is where the button went
+     * down */
+    ZoomSelect {
+        start: Option<Position>,
+    },
     Quit,
 }
 
+const MAX_COMMAND_HISTORY: usize = 100;
+/* The path that stands for the image piped to stdin */
+const STDIN_PATH: &str = "-";
+/* How far one key press zooms into and pans across a fractal, as a factor and a fraction of
+ * the visible width */
+const FRACTAL_ZOOM_STEP: f64 = 1.5;
+const FRACTAL_PAN_STEP: f64 = 0.1;
+/* Pixels between the lines of the grid until :grid changes it */
+const DEFAULT_GRID_SPACING: u32 = 100;
+/* About ten generations per second, the speed the Game of Life is usually shown at */
+const LIFE_GENERATION_DELAY: Duration = Duration::from_millis(100);
+const DEFAULT_FPS: u32 = 30;
+/* How long to wait for input when nothing on screen is moving */
+const IDLE_POLL: Duration = Duration::from_secs(1);
+/* How often --watch looks for changes while nothing else happens */
+const WATCH_POLL: Duration = Duration::from_millis(100);
+/* Used by --glitch without --glitch-probability */
+const DEFAULT_GLITCH_PROBABILITY: f32 = 0.05;
+
+/* Shown by `?`, keep in sync with `handle_normal_key_input` */
+const KEYBINDINGS: &[(&str, &str)] = &[
+    ("q, Esc", "quit"),
+    (":", "enter command mode"),
+    ("?", "show this help"),
+    ("+ / -", "zoom in / out"),
+    ("z", "drag a rectangle with the mouse to zoom into it"),
+    ("arrow keys", "pan"),
+    ("r / R", "rotate clockwise / counterclockwise"),
+    ("f", "flip horizontally"),
+    ("u", "undo zoom, pan or rotation"),
+    ("Ctrl-R", "redo zoom, pan or rotation"),
+    ("Ctrl-Z", "undo the last filter"),
+    ("h", "toggle the histogram"),
+    ("n", "toggle the minimap"),
+    ("M", "open where the photo was taken in $BROWSER"),
+    ("m<a-z>", "mark the current zoom, pan, rotation and effects"),
+    ("'<a-z>", "go back to a mark, in any tab"),
+    ("G", "toggle the coordinate grid"),
+    ("E", "show only the edges (see :edge)"),
+    ("c", "show only R, G, B, A, H, S or V in turn"),
+    ("v / V", "stronger / weaker vignette"),
+    ("< / >", "move the line of --split-view"),
+    ("[ / ]", "previous / next page of a TIFF or PDF"),
+    (
+        "Space, r, g",
+        "pause, randomize or show the generation of --life",
+    ),
+    (
+        "+ / -, h j k l",
+        "zoom and pan a fractal, which renders it again",
+    ),
+    (
+        "Tab / Shift-Tab",
+        "next / previous tab, or other side of a split",
+    ),
+    ("Ctrl-S", "pan both sides of a split together"),
+];
+
 #[derive(Debug)]
 struct App {
     mode: Mode,
     command_buffer: String,
-    image: Image,
+    /* Commands entered before, oldest first, and the one shown while browsing with Up/Down */
+    command_history: Vec<String>,
+    history_index: Option<usize>,
+    /* One per tab, each with its own zoom, pan and undo history */
+    images: Vec<Image>,
+    active: usize,
+    /* Indices of the left and right image when comparing side by side */
+    split: Option<(usize, usize)>,
+    /* Pan both sides of the split together */
+    sync_pan: bool,
+    /* Delay between images and when the next one is due */
+    slideshow: Option<Duration>,
+    next_slide: Instant,
     terminal: DefaultTerminal,
+    histogram: Option<Histogram>,
+    stats: Option<ImageStats>,
+    palette: Option<Palette>,
+    minimap: bool,
+    /* Spacing in image pixels of the coordinate grid, if it's shown */
+    grid: Option<u32>,
+    grid_spacing: u32,
+    /* Columns and rows the image is repeated in, e.g. to check that a texture is seamless */
+    tile: Option<(u16, u16)>,
+    sort_axis: SortAxis,
+    watchers: Vec<FileWatcher>,
+    signals: Signals,
+    /* The fractal being rendered in the background, if any */
+    fractal_render: Option<Receiver<(FractalView, DynamicImage)>>,
+    /* Zoom around the mouse pointer instead of the middle of the screen, and the last place the
+     * pointer was seen */
+    zoom_to_cursor: bool,
+    cursor: Option<(u16, u16)>,
+    /* When the Game of Life advances next, and whether its generation is in the status bar */
+    next_generation: Instant,
+    show_generation: bool,
+    /* Most redraws per second */
+    fps: u32,
+    /* --watermark, drawn over every image */
+    watermark: Option<(String, WatermarkPosition)>,
+    /* How tabs opened later treat EXIF data and ICC profiles, --strip-exif and
+     * --no-icc-convert */
+    open_options: OpenOptions,
+    /* --rotate-exif, :write turns the pixels by the EXIF orientation */
+    rotate_exif: bool,
+    /* Views marked with m, shared by all tabs and kept when images are reloaded */
+    marks: HashMap<char, ViewState>,
+    /* Sends the images drawn with the iTerm2 renderer, and whether they have to be sent again
+     * even if they didn't change */
+    iterm2: ITerm2Renderer,
+    iterm2_force: bool,
+    /* User defined keys from the config, each runs a command */
+    keybindings: BTreeMap<char, String>,
+    /* Applied to every image once it is decoded, see `prepare_image` */
+    startup: Option<Startup>,
 
+    /* Shown in the status bar for a single frame */
+    status_flash: Option<String>,
     footer_msg: Option<String>,
     foot_msg_expires: Option<Instant>,
 }
 
 impl App {
-    fn new(image_path: String, term: DefaultTerminal) -> Self {
+    fn new(
+        mut images: Vec<Image>,
+        term: DefaultTerminal,
+        capabilities: TerminalCapabilities,
+    ) -> Self {
+        for image in &mut images {
+            image.renderer = capabilities.best_renderer();
+            image.color_depth = capabilities.best_color_depth();
+        }
         Self {
             mode: Mode::Normal,
             command_buffer: String::new(),
-            image: Image::new(image_path.clone()),
+            command_history: Vec::new(),
+            history_index: None,
+            images,
+            active: 0,
+            split: None,
+            sync_pan: false,
+            slideshow: None,
+            next_slide: Instant::now(),
             terminal: term,
+            histogram: None,
+            stats: None,
+            palette: None,
+            minimap: false,
+            grid: None,
+            tile: None,
+            grid_spacing: DEFAULT_GRID_SPACING,
+            sort_axis: SortAxis::default(),
+            watchers: Vec::new(),
+            signals: Signals::default(),
+            fractal_render: None,
+            zoom_to_cursor: false,
+            cursor: None,
+            next_generation: Instant::now(),
+            show_generation: false,
+            fps: DEFAULT_FPS,
+            watermark: None,
+            open_options: OpenOptions::default(),
+            rotate_exif: false,
+            marks: HashMap::new(),
+            iterm2: ITerm2Renderer::default(),
+            iterm2_force: false,
+            keybindings: BTreeMap::new(),
+            startup: None,
+            status_flash: None,
             footer_msg: None,
             foot_msg_expires: None,
         }
     }
 
     fn run(mut self) -> Result<()> {
-        let tick_rate = Duration::from_millis(100);
+        let frame_time = Duration::from_secs_f64(1.0 / self.fps as f64);
+        let mut last_draw: Option<Instant> = None;
         while self.mode != Mode::Quit {
+            /* Never redraw faster than --fps, however quickly input comes in. Animation frames
+             * shown for less than that still come on time */
+            if let Some(since) = last_draw.map(|last| last.elapsed())
+                && since < frame_time
+            {
+                let now = Instant::now();
+                let next_frame = self
+                    .visible()
+                    .into_iter()
+                    .filter_map(|i| self.images[i].until_next_frame(now))
+                    .fold(frame_time - since, Duration::min);
+                thread::sleep(next_frame);
+            }
             /* Clear footer message if ti expired */
-            if let Some(expiry) = self.foot_msg_expires {
-                if Instant::now() >= expiry {
-                    self.footer_msg = None;
-                    self.foot_msg_expires = None;
+            if let Some(expiry) = self.foot_msg_expires
+                && Instant::now() >= expiry
+            {
+                self.footer_msg = None;
+                self.foot_msg_expires = None;
+            }
+
+            let changed: Vec<PathBuf> = self
+                .watchers
+                .iter()
+                .filter(|watcher| watcher.events.try_iter().count() > 0)
+                .map(|watcher| watcher.path.clone())
+                .collect();
+            for path in changed {
+                self.reload_image(&path);
+            }
+            if self.signals.take_reload() {
+                let path = PathBuf::from(&self.image().path);
+                self.reload_image(&path);
+            }
+            if self.signals.take_next() {
+                self.switch_tab(1);
+            }
+            if let Some((view, rendered)) = self
+                .fractal_render
+                .as_ref()
+                .and_then(|render| render.try_recv().ok())
+            {
+                self.fractal_render = None;
+                for image in self.images.iter_mut().filter(|i| i.fractal == Some(view)) {
+                    image.replace_image(rendered.clone());
                 }
+                self.refresh_histogram();
+            }
+
+            let now = Instant::now();
+            if let Some(delay) = self.slideshow
+                && now >= self.next_slide
+            {
+                self.switch_tab(1);
+                self.next_slide = now + delay;
+            }
+            self.load_visible();
+            for i in self.visible() {
+                self.images[i].tick(now);
+            }
+            if now >= self.next_generation {
+                self.step_life();
+                self.next_generation = now + LIFE_GENERATION_DELAY;
             }
 
+            /* Images drawn with the iTerm2 renderer and their cells, sent after the frame */
+            let mut inline: Vec<(usize, Rect)> = Vec::new();
             // Draw UI using only these locals inside the closure
             self.terminal.draw(|f| {
                 let area = f.area();
@@ -87,29 +411,226 @@ impl App {
                 let cmd_line = chunks[2];
 
                 // 1) Status
-                Text::from(format!("File: {}", self.image.path))
+                let image = &self.images[self.active];
+                let title = if self.images.len() > 1 {
+                    format!("[{}/{}] {}", self.active + 1, self.images.len(), image.path)
+                } else {
+                    format!("File: {}", image.path)
+                };
+                let title = match image.pages.len() {
+                    0 => title,
+                    pages => format!("{} - Page {}/{}", title, image.page + 1, pages),
+                };
+                let title = match image.frames.len() {
+                    0 => title,
+                    frames => format!("{} - Frame {}/{}", title, image.current + 1, frames),
+                };
+                let title = match image.gps {
+                    Some(gps) => format!("{} - GPS: {}", title, exif::format_gps(gps)),
+                    None => title,
+                };
+                let title = match image.channel() {
+                    Some(channel) => format!("{} - Channel {}", title, channel.name()),
+                    None => title,
+                };
+                let title = match image.vignette() {
+                    0.0 => title,
+                    strength => format!("{} - Vignette {:.1}", title, strength),
+                };
+                let title = match &image.life {
+                    Some(life) if self.show_generation => {
+                        format!("{} - Generation {}", title, life.generation)
+                    }
+                    _ => title,
+                };
+                let title = match &image.life {
+                    Some(life) if life.paused => format!("{} - paused", title),
+                    _ => title,
+                };
+                let title = match self.fractal_render {
+                    Some(_) => format!("{} - rendering", title),
+                    None => title,
+                };
+                let title = match self.slideshow {
+                    Some(_) => {
+                        let left = self.next_slide.saturating_duration_since(now);
+                        format!("{} - next in {}s", title, left.as_secs_f32().ceil())
+                    }
+                    None => title,
+                };
+                let status = match self.status_flash.take() {
+                    Some(flash) => format!("{} - {}", title, flash),
+                    None => title,
+                };
+                let status = match image.icc_converted {
+                    true => format!("[ICC→sRGB] {}", status),
+                    false => status,
+                };
+                let status = match self.open_options.strip_exif {
+                    true => format!("[PRIVATE] {}", status),
+                    false => status,
+                };
+                Text::from(status)
                     .centered()
                     .render(status_line, f.buffer_mut());
 
                 // 2) Image
-                self.image.render(main, f.buffer_mut());
+                match self.split {
+                    Some((left, right)) => {
+                        let [left_area, right_area] =
+                            Layout::horizontal([Percentage(50), Percentage(50)]).areas(main);
+                        for (i, area) in [(left, left_area), (right, right_area)] {
+                            let style = if i == self.active {
+                                Style::new().fg(Color::Yellow)
+                            } else {
+                                Style::new()
+                            };
+                            let block = Block::bordered().border_style(style);
+                            let inner = block.inner(area);
+                            block.render(area, f.buffer_mut());
+                            self.images[i].render(inner, f.buffer_mut());
+                            inline.push((i, self.images[i].drawn()));
+                        }
+                    }
+                    None => match self.tile {
+                        Some((cols, rows)) => {
+                            let (w, h) = (main.width / cols, main.height / rows);
+                            for row in 0..rows {
+                                for col in 0..cols {
+                                    let tile = Rect::new(main.x + col * w, main.y + row * h, w, h);
+                                    self.images[self.active].render(tile, f.buffer_mut());
+                                    inline.push((self.active, self.images[self.active].drawn()));
+                                }
+                            }
+                        }
+                        None => {
+                            self.images[self.active].render(main, f.buffer_mut());
+                            inline.push((self.active, self.images[self.active].drawn()));
+                        }
+                    },
+                }
+                if let Some(spacing) = self.grid {
+                    let image = &self.images[self.active];
+                    Grid { image, spacing }.render(main, f.buffer_mut());
+                }
+                if let Some((text, position)) = &self.watermark {
+                    for &(_, drawn) in &inline {
+                        let position = *position;
+                        Watermark { text, position }.render(drawn, f.buffer_mut());
+                    }
+                }
+                let mut corner = main;
+                if let Some(palette) = &self.palette {
+                    palette.render(main, f.buffer_mut());
+                    corner.height = corner.height.saturating_sub(Palette::HEIGHT);
+                }
+                if let Some(histogram) = &self.histogram {
+                    let area = corner;
+                    histogram.render(area, f.buffer_mut());
+                    /* Stack the minimap on top of the histogram instead of covering it */
+                    corner.height -= Histogram::height(area);
+                }
+                if self.minimap {
+                    Minimap(&self.images[self.active]).render(corner, f.buffer_mut());
+                }
+                if let Some(stats) = &self.stats {
+                    stats.render(main, f.buffer_mut());
+                }
 
                 // 3) Command line
                 let text = if let Some(msg) = &self.footer_msg {
                     msg.clone()
+                } else if let Mode::ZoomSelect { .. } = self.mode {
+                    "Drag a rectangle to zoom into, Esc to cancel".into()
                 } else if !self.command_buffer.is_empty() {
                     format!(":{}", self.command_buffer)
                 } else {
-                    "Press ':' to enter command mode, 'q' to quit".into()
+                    "Press ':' to enter command mode, '?' for help, 'q' to quit".into()
                 };
                 Text::from(text)
                     .left_aligned()
                     .render(cmd_line, f.buffer_mut());
+
+                if let Mode::Help { scroll } = self.mode {
+                    render_help(main, f.buffer_mut(), scroll);
+                }
             })?;
+            inline.retain(|&(i, area)| {
+                self.images[i].renderer == Renderer::ITerm2 && !area.is_empty()
+            });
+            let areas: Vec<Rect> = inline.iter().map(|&(_, area)| area).collect();
+            if !self.iterm2.same_areas(&areas) {
+                /* Old images stay on screen until their cells are written again, so clear it
+                 * and draw everything once more before sending the new ones */
+                self.terminal.clear()?;
+                self.iterm2.forget(&areas);
+                continue;
+            }
+            if !inline.is_empty() {
+                let sources: Vec<(iterm2::Source, Rect)> = inline
+                    .iter()
+                    .map(|&(i, area)| {
+                        let image = &self.images[i];
+                        let source = iterm2::Source {
+                            generation: image.generation(),
+                            region: image.visible_region(),
+                        };
+                        (source, area)
+                    })
+                    .collect();
+                let crop = |n: usize| {
+                    let (x, y, w, h) = sources[n].0.region;
+                    self.images[inline[n].0].displayed().crop_imm(x, y, w, h)
+                };
+                self.iterm2.draw(&sources, crop, self.iterm2_force)?;
+            }
+            self.iterm2_force = false;
+
+            last_draw = Some(Instant::now());
 
             // Input Handling
-            if event::poll(tick_rate)? {
-                if let Event::Key(key) = event::read()? {
+            /* Wake up in time for the next frame of an animation or generation of the Game of
+             * Life, and soon after a fractal is done rendering. Otherwise there is nothing to
+             * redraw until something happens */
+            let mut timeout = self
+                .visible()
+                .into_iter()
+                .filter_map(|i| self.images[i].until_next_frame(now))
+                .fold(IDLE_POLL, Duration::min);
+            if self
+                .visible()
+                .iter()
+                .any(|&i| self.images[i].life.is_some())
+            {
+                timeout = timeout.min(self.next_generation.saturating_duration_since(now));
+            }
+            if self.fractal_render.is_some() {
+                timeout = timeout.min(frame_time);
+            }
+            if self.slideshow.is_some() {
+                timeout = timeout.min(self.next_slide.saturating_duration_since(now));
+            }
+            if let Some(expiry) = self.foot_msg_expires {
+                timeout = timeout.min(expiry.saturating_duration_since(now));
+            }
+            /* Changed files only show up in a channel, which can't wake up the poll */
+            if !self.watchers.is_empty() {
+                timeout = timeout.min(WATCH_POLL);
+            }
+            if !event::poll(timeout)? {
+                continue;
+            }
+            let event = event::read()?;
+            /* Popups and resizing can write over the cells of inline images */
+            if matches!(event, Event::Key(_) | Event::Resize(..)) {
+                self.iterm2_force = true;
+            }
+            match event {
+                Event::Key(key) => {
+                    /* Give the user some time to look at the image they interacted with */
+                    if let Some(delay) = self.slideshow {
+                        self.next_slide = Instant::now() + delay * 2;
+                    }
                     // quit
                     if self.mode == Mode::Normal
                         && (key.code == KeyCode::Char('q') || key.code == KeyCode::Esc)
@@ -118,6 +639,15 @@ impl App {
                     }
                     self.handle_input(key);
                 }
+                /* Only reported with --zoom-to-cursor or while selecting with z, which turn on
+                 * mouse capture */
+                Event::Mouse(mouse) => {
+                    self.cursor = Some((mouse.column, mouse.row));
+                    if let Mode::ZoomSelect { start } = self.mode {
+                        self.handle_zoom_select(mouse, start);
+                    }
+                }
+                _ => {}
             }
         }
         Ok(())
@@ -127,6 +657,13 @@ impl App {
         match self.mode {
             Mode::Normal => self.handle_normal_key_input(key),
             Mode::Command => self.handle_command_key_input(key),
+            Mode::Help { scroll } => self.handle_help_key_input(key, scroll),
+            Mode::Mark { recall } => self.handle_mark_key_input(key, recall),
+            Mode::ZoomSelect { .. } => {
+                if key.code == KeyCode::Esc {
+                    self.end_zoom_select();
+                }
+            }
             Mode::Quit => {
                 /* The Program should quit now */
                 std::process::exit(0);
@@ -135,7 +672,37 @@ impl App {
     }
 
     fn handle_normal_key_input(&mut self, key: KeyEvent) {
+        if let KeyCode::Char(c) = key.code
+            && let Some(cmd) = self.keybindings.get(&c).cloned()
+        {
+            self.run_command(cmd);
+            return;
+        }
+        if let Some(view) = self.image().fractal
+            && let Some(view) = Self::navigate_fractal(view, key.code)
+        {
+            self.show_fractal(view);
+            return;
+        }
+        if self.handle_life_key(key.code) {
+            return;
+        }
+
         match key.code {
+            KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if self.image_mut().undo() {
+                    self.refresh_histogram();
+                } else {
+                    self.display_message("Nothing to undo".to_string());
+                }
+            }
+            KeyCode::Char('?') => self.mode = Mode::Help { scroll: 0 },
+            KeyCode::Char('z') => {
+                if !self.zoom_to_cursor {
+                    let _ = std::io::stdout().execute(EnableMouseCapture);
+                }
+                self.mode = Mode::ZoomSelect { start: None };
+            }
             KeyCode::Char(':') => {
                 self.mode = Mode::Command;
                 self.command_buffer.clear();
@@ -143,33 +710,309 @@ impl App {
             KeyCode::Esc => {
                 self.mode = Mode::Normal;
             }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.sync_pan = !self.sync_pan;
+                let state = if self.sync_pan { "on" } else { "off" };
+                self.display_message(format!("Synchronized panning {}", state));
+            }
+            /* In a split, Tab moves the focus to the other side instead */
+            KeyCode::Tab | KeyCode::BackTab if self.split.is_some() => self.toggle_split_focus(),
+            KeyCode::Tab => self.switch_tab(1),
+            KeyCode::BackTab => self.switch_tab(-1),
+            KeyCode::Char('u') => {
+                let changed = self.image_mut().undo_view();
+                if !changed {
+                    self.display_message("Nothing to undo".to_string());
+                }
+            }
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let changed = self.image_mut().redo_view();
+                if !changed {
+                    self.display_message("Nothing to redo".to_string());
+                }
+            }
             KeyCode::Char('+') => {
-                self.image.zoom *= 1.1;
+                self.image_mut().save_view();
+                self.zoom(1.1);
             }
             KeyCode::Char('-') => {
-                if self.image.zoom * 0.9 < 1.0 {
+                if self.image().zoom * 0.9 < 1.0 {
                     self.display_message(("Can't zoom out anymore!").to_string());
                 } else {
-                    self.image.zoom *= 0.9;
+                    self.image_mut().save_view();
+                    self.zoom(0.9);
                 }
             }
-            KeyCode::Up => {
-                self.image.pan_y -= 10;
+            KeyCode::Char('r') => {
+                self.image_mut().save_view();
+                self.image_mut().transform.rotate_cw();
             }
-            KeyCode::Down => {
-                self.image.pan_y += 10;
+            KeyCode::Char('R') => {
+                self.image_mut().save_view();
+                self.image_mut().transform.rotate_ccw();
             }
-            KeyCode::Left => {
-                self.image.pan_x -= 10;
+            KeyCode::Char('f') => {
+                self.image_mut().save_view();
+                let transform = &mut self.image_mut().transform;
+                transform.flip_horizontal = !transform.flip_horizontal;
             }
-            KeyCode::Right => {
-                self.image.pan_x += 10;
+            KeyCode::Char('h') => {
+                self.histogram = match self.histogram {
+                    Some(_) => None,
+                    None => Some(Histogram::new(&self.image().image)),
+                };
             }
+            KeyCode::Char('n') => self.minimap = !self.minimap,
+            KeyCode::Char('M') => self.open_map(),
+            KeyCode::Char('m') => self.mode = Mode::Mark { recall: false },
+            KeyCode::Char('\'') => self.mode = Mode::Mark { recall: true },
+            KeyCode::Char('G') => {
+                self.grid = match self.grid {
+                    Some(_) => None,
+                    None => Some(self.grid_spacing),
+                }
+            }
+            KeyCode::Char('E') => {
+                self.image_mut().save_view();
+                self.image_mut().toggle_edges();
+            }
+            KeyCode::Char('c') => {
+                self.image_mut().save_view();
+                let channel = Channel::cycle(self.image().channel());
+                self.image_mut().set_channel(channel);
+            }
+            KeyCode::Char('v') => self.change_vignette(0.1),
+            KeyCode::Char('V') => self.change_vignette(-0.1),
+            KeyCode::Char('<') => self.image_mut().move_split(-0.05),
+            KeyCode::Char('>') => self.image_mut().move_split(0.05),
+            KeyCode::Char(']') => self.turn_page(1),
+            KeyCode::Char('[') => self.turn_page(-1),
+            KeyCode::Up => self.pan(0, -10),
+            KeyCode::Down => self.pan(0, 10),
+            KeyCode::Left => self.pan(-10, 0),
+            KeyCode::Right => self.pan(10, 0),
 
             _ => {}
         }
     }
 
+    /* Space, r and g do something else for the Game of Life, returns false for other keys */
+    fn handle_life_key(&mut self, key: KeyCode) -> bool {
+        let image = self.image_mut();
+        let Some(life) = image.life.as_mut() else {
+            return false;
+        };
+        match key {
+            KeyCode::Char(' ') => life.paused = !life.paused,
+            KeyCode::Char('r') => {
+                life.randomize();
+                let cells = life.image();
+                image.replace_image(cells);
+            }
+            KeyCode::Char('g') => self.show_generation = !self.show_generation,
+            _ => return false,
+        }
+        true
+    }
+
+    /* Every visible Game of Life that isn't paused moves on one generation */
+    fn step_life(&mut self) {
+        for i in self.visible() {
+            let image = &mut self.images[i];
+            if let Some(life) = image.life.as_mut()
+                && !life.paused
+            {
+                life.step();
+                let cells = life.image();
+                image.replace_image(cells);
+            }
+        }
+    }
+
+    /* The current image stays on screen until `view` is rendered */
+    fn show_fractal(&mut self, view: FractalView) {
+        self.image_mut().path = view.kind.name().to_string();
+        self.image_mut().fractal = Some(view);
+        self.fractal_render = Some(fractal::render_in_background(view));
+    }
+
+    /* Zooming and panning a fractal moves through the complex plane and renders it again, so
+     * there is always detail to see. None for keys that do something else */
+    fn navigate_fractal(view: FractalView, key: KeyCode) -> Option<FractalView> {
+        match key {
+            KeyCode::Char('+') => Some(view.zoomed(FRACTAL_ZOOM_STEP)),
+            KeyCode::Char('-') => Some(view.zoomed(1.0 / FRACTAL_ZOOM_STEP)),
+            KeyCode::Char('h') | KeyCode::Left => Some(view.panned(-FRACTAL_PAN_STEP, 0.0)),
+            KeyCode::Char('l') | KeyCode::Right => Some(view.panned(FRACTAL_PAN_STEP, 0.0)),
+            KeyCode::Char('k') | KeyCode::Up => Some(view.panned(0.0, -FRACTAL_PAN_STEP)),
+            KeyCode::Char('j') | KeyCode::Down => Some(view.panned(0.0, FRACTAL_PAN_STEP)),
+            _ => None,
+        }
+    }
+
+    /* Saves every frame to `dir` and the delays to delays.txt, one line in milliseconds per
+     * frame. Blocks until done, keys other than Esc are dropped meanwhile. Returns how many
+     * frames were saved, or None when it was cancelled */
+    fn export_frames(&mut self, dir: &Path) -> Result<Option<usize>> {
+        let count = self.image().frames.len();
+        if count == 0 {
+            color_eyre::eyre::bail!("{} is not an animation", self.image().path);
+        }
+        fs::create_dir_all(dir)?;
+        /* The progress line is all that's drawn, so inline images have to be sent again */
+        self.iterm2_force = true;
+        for i in 0..count {
+            while event::poll(Duration::ZERO)? {
+                if let Event::Key(key) = event::read()?
+                    && key.code == KeyCode::Esc
+                {
+                    return Ok(None);
+                }
+            }
+            self.terminal.draw(|f| {
+                let status = format!(
+                    "Exporting frame {}/{} ({}%) - Esc to cancel",
+                    i + 1,
+                    count,
+                    i * 100 / count
+                );
+                f.render_widget(
+                    Text::from(status).centered(),
+                    Rect {
+                        height: 1,
+                        ..f.area()
+                    },
+                );
+            })?;
+            let frame = self.image_mut().render_frame(i).to_rgba8();
+            ::image::save_buffer(
+                dir.join(format!("frame_{:03}.png", i)),
+                &frame,
+                frame.width(),
+                frame.height(),
+                ::image::ExtendedColorType::Rgba8,
+            )?;
+        }
+        let delays: String = self
+            .image()
+            .frames
+            .iter()
+            .map(|(_, delay)| format!("{}\n", delay.as_millis()))
+            .collect();
+        fs::write(dir.join("delays.txt"), delays)?;
+        Ok(Some(count))
+    }
+
+    /* Shows the GPS position of the photo on a map in the browser */
+    fn open_map(&mut self) {
+        let Some((lat, lon)) = self.image().gps else {
+            self.display_message("The image has no GPS position".to_string());
+            return;
+        };
+        let Result::Ok(browser) = std::env::var("BROWSER") else {
+            self.display_message("Set $BROWSER to open maps".to_string());
+            return;
+        };
+        let url = format!("https://maps.google.com/?q={:.6},{:.6}", lat, lon);
+        /* The browser must not write over the screen */
+        let spawned = std::process::Command::new(&browser)
+            .arg(&url)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        if let Err(e) = spawned {
+            self.display_message(format!("Failed to run {}: {}", browser, e));
+        }
+    }
+
+    fn handle_mark_key_input(&mut self, key: KeyEvent, recall: bool) {
+        self.mode = Mode::Normal;
+        let KeyCode::Char(mark @ 'a'..='z') = key.code else {
+            return;
+        };
+        if !recall {
+            self.marks.insert(mark, self.image().view());
+            self.status_flash = Some(format!("Mark '{}' set", mark));
+            return;
+        }
+        match self.marks.get(&mark) {
+            Some(&view) => self.image_mut().restore_view(view),
+            None => self.display_message(format!("Mark '{}' is not set", mark)),
+        }
+    }
+
+    fn handle_zoom_select(&mut self, mouse: MouseEvent, start: Option<Position>) {
+        let end = Position::new(mouse.column, mouse.row);
+        match (mouse.kind, start) {
+            (MouseEventKind::Down(MouseButton::Left), _) => {
+                self.mode = Mode::ZoomSelect { start: Some(end) }
+            }
+            (MouseEventKind::Up(MouseButton::Left), Some(start)) => {
+                self.zoom_to_cells(start, end);
+                self.end_zoom_select();
+            }
+            _ => {}
+        }
+    }
+
+    fn end_zoom_select(&mut self) {
+        if !self.zoom_to_cursor {
+            let _ = std::io::stdout().execute(DisableMouseCapture);
+        }
+        self.mode = Mode::Normal;
+    }
+
+    /* Zooms so the image under the cells from `a` to `b` fills the view. Anything smaller than
+     * 2x2 cells counts as a click, which zooms in twice as far around that point */
+    fn zoom_to_cells(&mut self, a: Position, b: Position) {
+        let drawn = self.image().drawn();
+        if drawn.is_empty() {
+            return;
+        }
+        let (x0, y0, w, h) = self.image().visible_region();
+        /* Cells outside of the image are moved onto its edge */
+        let to_image = |cell: Position| {
+            let fx = (cell.x.clamp(drawn.left(), drawn.right() - 1) - drawn.x) as f32 + 0.5;
+            let fy = (cell.y.clamp(drawn.top(), drawn.bottom() - 1) - drawn.y) as f32 + 0.5;
+            (fx / drawn.width as f32, fy / drawn.height as f32)
+        };
+        let (a, b) = (to_image(a), to_image(b));
+        let cells = (
+            ((a.0 - b.0).abs() * drawn.width as f32).round(),
+            ((a.1 - b.1).abs() * drawn.height as f32).round(),
+        );
+        let image = self.image_mut();
+        image.save_view();
+        if cells.0 < 2.0 || cells.1 < 2.0 {
+            image.zoom_around(2.0, b);
+            return;
+        }
+        let point = |(fx, fy): (f32, f32)| (x0 as f32 + fx * w as f32, y0 as f32 + fy * h as f32);
+        let (a, b) = (point(a), point(b));
+        image.focus((
+            a.0.min(b.0) as u32,
+            a.1.min(b.1) as u32,
+            (a.0 - b.0).abs().ceil() as u32,
+            (a.1 - b.1).abs().ceil() as u32,
+        ));
+    }
+
+    fn handle_help_key_input(&mut self, key: KeyEvent, scroll: usize) {
+        match key.code {
+            KeyCode::Char('?') | KeyCode::Esc => self.mode = Mode::Normal,
+            KeyCode::Char('j') | KeyCode::Down => {
+                let scroll = (scroll + 1).min(KEYBINDINGS.len() - 1);
+                self.mode = Mode::Help { scroll };
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                let scroll = scroll.saturating_sub(1);
+                self.mode = Mode::Help { scroll };
+            }
+            _ => {}
+        }
+    }
+
     fn handle_command_key_input(&mut self, key: KeyEvent) {
         match key.code {
             KeyCode::Backspace => {
@@ -179,6 +1022,13 @@ impl App {
                 let cmd = self.command_buffer.clone();
                 // TODO: execute Add commands
 
+                if !cmd.trim().is_empty() && self.command_history.last() != Some(&cmd) {
+                    if self.command_history.len() == MAX_COMMAND_HISTORY {
+                        self.command_history.remove(0);
+                    }
+                    self.command_history.push(cmd.clone());
+                }
+                self.history_index = None;
                 self.run_command(cmd);
 
                 self.mode = Mode::Normal;
@@ -187,7 +1037,30 @@ impl App {
             KeyCode::Esc => {
                 self.mode = Mode::Normal;
                 self.command_buffer.clear();
+                self.history_index = None;
+            }
+            KeyCode::Up => {
+                let index = match self.history_index {
+                    None => self.command_history.len().checked_sub(1),
+                    Some(i) => Some(i.saturating_sub(1)),
+                };
+                if let Some(i) = index {
+                    self.history_index = index;
+                    self.command_buffer = self.command_history[i].clone();
+                }
             }
+            KeyCode::Down => match self.history_index {
+                Some(i) if i + 1 < self.command_history.len() => {
+                    self.history_index = Some(i + 1);
+                    self.command_buffer = self.command_history[i + 1].clone();
+                }
+                /* Past the newest entry, back to an empty line like a shell */
+                Some(_) => {
+                    self.history_index = None;
+                    self.command_buffer.clear();
+                }
+                None => {}
+            },
             KeyCode::Char(c) => {
                 self.command_buffer.push(c);
             }
@@ -196,25 +1069,516 @@ impl App {
     }
 
     fn run_command(&mut self, cmd: String) {
-        match cmd.trim().to_lowercase().as_str() {
-            "q" | "quit" => {
+        let lower = cmd.trim().to_lowercase();
+        let args: Vec<&str> = lower.split_whitespace().collect();
+        match args.as_slice() {
+            ["q"] | ["quit"] => {
                 self.mode = Mode::Quit;
             }
-            "help" => {
+            ["help"] => {
                 self.display_message(
                     "Commands:\n\
                     q, quit          - exit the program\n\
                     help             - show this message\n\
+                    filter <name>    - apply a filter (cartoon <k>, datamosh <q>,\n\
+                                       denoise <strength>,\n\
+                                       channels <c>, equalize <mode>,\n\
+                                       clahe <clip> <tile>,\n\
+                                       split-tone <shadow-hue> <highlight-hue>,\n\
+                                       hsl <h> <s> <l>, vhs, ca <px>,\n\
+                                       glitch <probability>, crt, barrel <k>,\n\
+                                       scanlines <darken>, phosphor, glow <sigma>,\n\
+                                       kaleidoscope <n>, edge sobel|canny,\n\
+                                       blur <radius>,\n\
+                                       sharpen <amount> [<radius> <threshold>],\n\
+                                       vignette <strength>, posterize <levels>,\n\
+                                       autocrop [<threshold>], round-corners <r>,\n\
+                                       drop-shadow <x> <y> <blur> <color>)\n\
+                    filter add <name> - run a filter on every redraw instead, after\n\
+                                       the ones added before\n\
+                    filter list      - show the filters added with filter add\n\
+                    filter remove <n> - remove the nth filter of filter list\n\
+                    filter move <from> <to> - move a filter to another place\n\
+                    filter clear     - remove the filters added with filter add\n\
+                    hsl <h> <s> <l>  - same as filter hsl\n\
+                    blur <radius>    - same as filter blur\n\
+                    sharpen <amount> - same as filter sharpen\n\
+                    denoise <radius> - same as filter denoise\n\
+                    posterize <n>    - same as filter posterize\n\
+                    rotate <deg>     - rotate by 0, 90, 180 or 270 degrees\n\
+                    flip h|v         - flip horizontally or vertically\n\
+                    reset            - undo all zoom, pan and rotation (u undoes,\n\
+                                       Ctrl-R redoes view changes)\n\
+                    sort-pixels <by> - sort pixels by hue, luma or saturation\n\
+                    goto <n>         - jump to a page of a TIFF or PDF, or a frame\n\
+                                       of an animation\n\
+                    palette <n>|off  - show the n most common colors\n\
+                    mirror x|y|off   - reflect the left or top half of the screen\n\
+                    grid <n>|off     - lines every n pixels (G toggles)\n\
+                    stats            - show or hide mean, deviation, range and\n\
+                                       clipping of every channel\n\
+                    tile <c>x<r>|off - repeat the image in c columns and r rows\n\
+                    julia <cx> <cy>  - show the Julia set of another constant\n\
+                    edge [sobel|canny [<low> <high>]|off]\n\
+                                     - show only the edges (also E)\n\
+                    edge color|white - draw the edges in the image's colors or white\n\
+                    open <path>      - open an image in a new tab\n\
+                    close            - close the current tab\n\
+                    next, prev       - switch tabs (also Tab and Shift-Tab)\n\
+                    split [path]     - compare with another image side by side\n\
+                    unsplit          - go back to a single image\n\
+                    slideshow <sec>  - show the next image every sec seconds\n\
+                    slideshow stop   - stop the slideshow\n\
+                    set vibrance <v> - saturate muted colors, -1.0 to 1.0\n\
+                    vignette <s>     - darken the corners, 0.0 to 1.0 (also v and V)\n\
+                    channel <c>|off  - show only r, g, b, a, h, s, v, y, cb or cr\n\
+                                       after the filters (also c)\n\
+                    onion [path]     - overlay an image at half opacity, or remove it\n\
+                    write <path>     - save the image with its filters, without any\n\
+                                       metadata\n\
+                    export-frames <dir> - save every frame of an animation as it's\n\
+                                       shown to dir/frame_000.png and so on, and\n\
+                                       their delays to dir/delays.txt (Esc cancels)\n\
                     ..."
                     .to_string(),
                 );
             }
+            ["rotate", deg] => match deg.parse::<u16>() {
+                Result::Ok(deg @ (0 | 90 | 180 | 270)) => {
+                    self.image_mut().save_view();
+                    self.image_mut().transform.rotation = deg;
+                }
+                _ => self.display_message(format!("Invalid rotation: {}", deg)),
+            },
+            ["flip", "h"] => {
+                self.image_mut().save_view();
+                let transform = &mut self.image_mut().transform;
+                transform.flip_horizontal = !transform.flip_horizontal;
+            }
+            ["flip", "v"] => {
+                self.image_mut().save_view();
+                let transform = &mut self.image_mut().transform;
+                transform.flip_vertical = !transform.flip_vertical;
+            }
+            ["reset"] => self.image_mut().reset_view(),
+            ["slideshow", "stop"] => self.slideshow = None,
+            ["slideshow", seconds] => match cli::slideshow_delay(seconds) {
+                Result::Ok(delay) => self.start_slideshow(delay),
+                Err(e) => self.display_message(format!("Invalid delay {}: {}", seconds, e)),
+            },
+            ["palette", "off"] => self.palette = None,
+            ["palette", n] => match n.parse::<usize>() {
+                Result::Ok(n) if n >= 1 => {
+                    self.palette = Some(Palette::new(&self.image().image, n))
+                }
+                _ => self.display_message(format!("Invalid number of colors: {}", n)),
+            },
+            ["mirror", "off"] => self.image_mut().set_mirror(None),
+            ["mirror", axis] => match MirrorAxis::parse(axis) {
+                Result::Ok(axis) => self.image_mut().set_mirror(Some(axis)),
+                Err(e) => self.display_message(e),
+            },
+            ["julia", cx, cy] => match (cx.parse::<f64>(), cy.parse::<f64>()) {
+                (Result::Ok(cx), Result::Ok(cy)) => match self.image().fractal {
+                    Some(view) => self.show_fractal(FractalView {
+                        kind: FractalKind::Julia { c: (cx, cy) },
+                        ..view
+                    }),
+                    None => self.display_message(
+                        "Not a fractal, start with --julia or --mandelbrot".to_string(),
+                    ),
+                },
+                _ => self.display_message(format!("Invalid constant: {} {}", cx, cy)),
+            },
+            ["stats"] => {
+                self.stats = match self.stats {
+                    Some(_) => None,
+                    None => Some(compute_stats(&self.image().image)),
+                }
+            }
+            ["tile", "off"] => self.tile = None,
+            ["tile", size] => match size.split_once('x').map(|(c, r)| (c.parse(), r.parse())) {
+                Some((Result::Ok(cols @ 1..), Result::Ok(rows @ 1..))) => {
+                    let image = self.image_mut();
+                    image.save_view();
+                    (image.pan_x, image.pan_y) = (0, 0);
+                    self.tile = Some((cols, rows));
+                }
+                _ => self.display_message(format!("Expected <cols>x<rows>: {}", size)),
+            },
+            ["grid", "off"] => self.grid = None,
+            ["grid", spacing] => match spacing.parse::<u32>() {
+                Result::Ok(spacing @ 1..) => {
+                    self.grid_spacing = spacing;
+                    self.grid = Some(spacing);
+                }
+                _ => self.display_message(format!("Invalid grid spacing: {}", spacing)),
+            },
+            ["edge"] => self.image_mut().toggle_edges(),
+            ["edge", "off"] => self.image_mut().set_edges(None),
+            ["edge", "color"] => self.image_mut().set_edge_colors(true),
+            ["edge", "white"] => self.image_mut().set_edge_colors(false),
+            ["edge", algorithm @ ..] => match EdgeAlgorithm::parse(algorithm) {
+                Result::Ok(algorithm) => self.image_mut().set_edges(Some(algorithm)),
+                Err(e) => self.display_message(e),
+            },
+            /* Pages and frames count from 1 here, like in the status bar */
+            ["goto", n] if !self.image().frames.is_empty() => {
+                let seeked = n
+                    .parse::<usize>()
+                    .is_ok_and(|n| n >= 1 && self.image_mut().seek(n - 1));
+                if seeked {
+                    self.refresh_histogram();
+                } else {
+                    self.display_message(format!("No such frame: {}", n));
+                }
+            }
+            ["goto", page] => {
+                let turned = page
+                    .parse::<usize>()
+                    .is_ok_and(|page| page >= 1 && self.image_mut().set_page(page - 1));
+                if turned {
+                    self.refresh_histogram();
+                } else {
+                    self.display_message(format!("No such page: {}", page));
+                }
+            }
+            ["next"] => self.switch_tab(1),
+            ["prev"] => self.switch_tab(-1),
+            /* The path is taken from the original input, since `args` is lowercased */
+            ["open", _, ..] => self.open_tab(cmd.trim()["open".len()..].trim().to_string()),
+            ["close"] => self.close_tab(),
+            ["export-frames", _, ..] => {
+                let dir = PathBuf::from(cmd.trim()["export-frames".len()..].trim());
+                match self.export_frames(&dir) {
+                    Result::Ok(Some(count)) => {
+                        self.status_flash =
+                            Some(format!("Exported {} frames to {}", count, dir.display()))
+                    }
+                    Result::Ok(None) => self.status_flash = Some("Export cancelled".to_string()),
+                    Err(e) => self.display_message(format!("Failed to export frames: {}", e)),
+                }
+            }
+            ["write", _, ..] => {
+                let path = PathBuf::from(cmd.trim()["write".len()..].trim());
+                let image = match self.rotate_exif {
+                    true => self.image().upright(),
+                    false => Cow::Borrowed(&self.image().image),
+                };
+                match save_image(&image, &path) {
+                    Result::Ok(()) => self.status_flash = Some(format!("Wrote {}", path.display())),
+                    Err(e) => self.display_message(format!("Failed to write image: {}", e)),
+                }
+            }
+            ["split"] => {
+                let copy = self.image().clone();
+                self.split_with(copy);
+            }
+            ["split", _, ..] => match Image::open(
+                cmd.trim()["split".len()..].trim().to_string(),
+                self.open_options,
+            ) {
+                Result::Ok(mut image) => {
+                    image.renderer = self.image().renderer;
+                    image.color_depth = self.image().color_depth;
+                    self.split_with(image);
+                }
+                Err(e) => self.display_message(format!("Failed to open image: {}", e)),
+            },
+            ["unsplit"] => self.split = None,
+            ["set", "vibrance", amount] => match amount.parse::<f32>() {
+                Result::Ok(amount) if (-1.0..=1.0).contains(&amount) => {
+                    self.image_mut().set_vibrance(amount)
+                }
+                _ => self.display_message(format!("Vibrance must be -1.0 to 1.0: {}", amount)),
+            },
+            ["channel", "off"] => self.image_mut().set_channel(None),
+            ["channel", channel] => match Channel::parse(channel) {
+                Result::Ok(channel) => self.image_mut().set_channel(Some(channel)),
+                Err(e) => self.display_message(e),
+            },
+            ["vignette", strength] => match strength.parse::<f32>() {
+                Result::Ok(strength) if (0.0..=1.0).contains(&strength) => {
+                    self.image_mut().set_vignette(strength)
+                }
+                _ => self.display_message(format!(
+                    "Vignette strength must be 0.0 to 1.0: {}",
+                    strength
+                )),
+            },
+            ["onion"] => self.image_mut().set_onion_skin(None),
+            ["onion", _, ..] => match ::image::open(cmd.trim()["onion".len()..].trim()) {
+                Result::Ok(onion) => self.image_mut().set_onion_skin(Some(onion)),
+                Err(e) => self.display_message(format!("Failed to open image: {}", e)),
+            },
+            ["sort-pixels", key] => match SortKey::parse(key) {
+                Result::Ok(key) => self.apply_filter(&Filter::SortPixels {
+                    key,
+                    axis: self.sort_axis,
+                    threshold: None,
+                }),
+                Err(e) => self.display_message(e),
+            },
+            ["hsl" | "blur" | "sharpen" | "denoise" | "posterize", ..] => {
+                match Filter::parse(&args) {
+                    Result::Ok(filter) => self.apply_filter(&filter),
+                    Err(e) => self.display_message(e),
+                }
+            }
+            /* Shown on screen rather than applied once, so animations glitch differently on
+             * every frame */
+            ["filter", "glitch", probability] => match probability.parse::<f32>() {
+                Result::Ok(probability) if (0.0..=1.0).contains(&probability) => {
+                    self.image_mut().set_glitch(probability)
+                }
+                _ => self.display_message(format!(
+                    "Glitch probability must be 0.0 to 1.0: {}",
+                    probability
+                )),
+            },
+            ["filter", "add", rest @ ..] => match Filter::parse(rest) {
+                Result::Ok(filter) => {
+                    let count = self.image_mut().add_pipeline_filter(rest.join(" "), filter);
+                    self.status_flash = Some(format!("{} filters in the pipeline", count));
+                }
+                Err(e) => self.display_message(e),
+            },
+            ["filter", "list"] => {
+                let pipeline = self.image().pipeline();
+                let msg = if pipeline.is_empty() {
+                    "No filters in the pipeline, add one with :filter add <name>".to_string()
+                } else {
+                    pipeline
+                        .names()
+                        .enumerate()
+                        .map(|(i, name)| format!("{}: {}", i + 1, name))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                };
+                self.display_message(msg);
+            }
+            ["filter", "remove", n] => {
+                /* Numbered from 1, like in :filter list */
+                let removed = n
+                    .parse::<usize>()
+                    .is_ok_and(|n| n >= 1 && self.image_mut().remove_pipeline_filter(n - 1));
+                if !removed {
+                    self.display_message(format!("No filter number {} in the pipeline", n));
+                }
+            }
+            ["filter", "move", from, to] => {
+                let moved = match (from.parse::<usize>(), to.parse::<usize>()) {
+                    (Result::Ok(from @ 1..), Result::Ok(to @ 1..)) => {
+                        self.image_mut().move_pipeline_filter(from - 1, to - 1)
+                    }
+                    _ => false,
+                };
+                if !moved {
+                    self.display_message(format!("Can't move filter {} to {}", from, to));
+                }
+            }
+            ["filter", "clear"] => self.image_mut().clear_pipeline(),
+            ["filter", rest @ ..] => match Filter::parse(rest) {
+                Result::Ok(filter) => self.apply_filter(&filter),
+                Err(e) => self.display_message(e),
+            },
             _ => {
                 self.display_message(format!("Unknown command: {}", cmd));
             }
         }
     }
 
+    /* Editors and image generators often replace the file atomically, so it may be missing
+     * for a moment. Keep retrying for a bit before giving up */
+    fn reload_image(&mut self, path: &Path) {
+        for i in 0..self.images.len() {
+            /* Tabs that weren't shown yet read the new file when they are */
+            if Path::new(&self.images[i].path) != path || !self.images[i].is_loaded() {
+                continue;
+            }
+            let start = Instant::now();
+            loop {
+                match self.images[i].reload() {
+                    Result::Ok(()) => {
+                        self.refresh_histogram();
+                        self.status_flash = Some("Reloaded".to_string());
+                        break;
+                    }
+                    Err(e) if start.elapsed() >= Duration::from_millis(500) => {
+                        self.display_message(format!("Failed to reload: {}", e));
+                        break;
+                    }
+                    Err(_) => thread::sleep(Duration::from_millis(50)),
+                }
+            }
+        }
+    }
+
+    /* Decodes the tabs on screen that weren't shown before */
+    fn load_visible(&mut self) {
+        for i in self.visible() {
+            if self.images[i].is_loaded() {
+                continue;
+            }
+            match self.images[i].load() {
+                Result::Ok(()) => {
+                    self.prepare_image(i);
+                    self.refresh_histogram();
+                }
+                Err(e) => {
+                    let msg = format!("Failed to open {}: {}", self.images[i].path, e);
+                    self.display_message(msg);
+                }
+            }
+        }
+    }
+
+    fn prepare_image(&mut self, i: usize) {
+        let Some(startup) = &self.startup else {
+            return;
+        };
+        if let Some(e) = prepare_image(&mut self.images[i], &startup.args, startup.onion.as_ref()) {
+            self.display_message(e);
+        }
+    }
+
+    /* Indices of the images on screen */
+    fn visible(&self) -> Vec<usize> {
+        match self.split {
+            Some((left, right)) => vec![left, right],
+            None => vec![self.active],
+        }
+    }
+
+    fn image(&self) -> &Image {
+        &self.images[self.active]
+    }
+
+    fn image_mut(&mut self) -> &mut Image {
+        &mut self.images[self.active]
+    }
+
+    /* Zooms around the pixel under the mouse pointer with --zoom-to-cursor, and around the
+     * middle of the image when the pointer is somewhere else or wasn't seen yet */
+    fn zoom(&mut self, factor: f32) {
+        let drawn = self.image().drawn();
+        let anchor = match self.cursor {
+            Some((x, y)) if self.zoom_to_cursor && drawn.contains((x, y).into()) => (
+                (x - drawn.x) as f32 / drawn.width as f32,
+                (y - drawn.y) as f32 / drawn.height as f32,
+            ),
+            _ => (0.5, 0.5),
+        };
+        self.image_mut().zoom_around(factor, anchor);
+    }
+
+    fn pan(&mut self, dx: i32, dy: i32) {
+        /* Every tile shows the middle of the image */
+        if self.tile.is_some() {
+            return;
+        }
+        let image = self.image_mut();
+        image.save_view();
+        image.pan_x += dx;
+        image.pan_y += dy;
+        if self.sync_pan
+            && let Some((left, right)) = self.split
+        {
+            let other = if self.active == left { right } else { left };
+            self.images[other].save_view();
+            self.images[other].pan_x += dx;
+            self.images[other].pan_y += dy;
+        }
+    }
+
+    /* Shows `image` next to the current one. It is added as a tab, so it can still be reached
+     * after the split is closed */
+    fn split_with(&mut self, image: Image) {
+        self.images.push(image);
+        self.split = Some((self.active, self.images.len() - 1));
+    }
+
+    fn start_slideshow(&mut self, delay: Duration) {
+        self.slideshow = Some(delay);
+        self.next_slide = Instant::now() + delay;
+    }
+
+    fn toggle_split_focus(&mut self) {
+        if let Some((left, right)) = self.split {
+            self.active = if self.active == left { right } else { left };
+            self.refresh_histogram();
+        }
+    }
+
+    /* Moves `by` pages forward in a multi-page document, stopping at the first and last page */
+    fn turn_page(&mut self, by: isize) {
+        let image = self.image_mut();
+        let last = image.pages.len().saturating_sub(1) as isize;
+        let page = (image.page as isize + by).clamp(0, last) as usize;
+        if page != image.page && image.set_page(page) {
+            self.refresh_histogram();
+        }
+    }
+
+    /* Moves `by` tabs forward, wrapping around at either end */
+    fn switch_tab(&mut self, by: isize) {
+        self.split = None;
+        let len = self.images.len() as isize;
+        self.active = (self.active as isize + by).rem_euclid(len) as usize;
+        self.refresh_histogram();
+    }
+
+    fn open_tab(&mut self, path: String) {
+        match Image::open(path, self.open_options) {
+            Result::Ok(mut image) => {
+                self.split = None;
+                /* New tabs are rendered like the current one */
+                image.renderer = self.image().renderer;
+                image.color_depth = self.image().color_depth;
+                self.images.push(image);
+                self.active = self.images.len() - 1;
+                self.refresh_histogram();
+            }
+            Err(e) => self.display_message(format!("Failed to open image: {}", e)),
+        }
+    }
+
+    fn close_tab(&mut self) {
+        if self.images.len() == 1 {
+            self.display_message("Can't close the last image".to_string());
+            return;
+        }
+        self.split = None;
+        self.images.remove(self.active);
+        self.active = self.active.min(self.images.len() - 1);
+        self.refresh_histogram();
+    }
+
+    /* Steps of `by`, rounded so repeated presses land on 0.1, 0.2, ... exactly */
+    fn change_vignette(&mut self, by: f32) {
+        self.image_mut().save_view();
+        let strength = ((self.image().vignette() + by) * 10.0).round() / 10.0;
+        self.image_mut().set_vignette(strength);
+    }
+
+    fn apply_filter(&mut self, filter: &Filter) {
+        self.image_mut().apply_filter(filter);
+        self.refresh_histogram();
+    }
+
+    /* Keep a visible histogram, palette and statistics in sync after the image changed */
+    fn refresh_histogram(&mut self) {
+        if self.histogram.is_some() {
+            self.histogram = Some(Histogram::new(&self.image().image));
+        }
+        if let Some(size) = self.palette.as_ref().map(|palette| palette.size) {
+            self.palette = Some(Palette::new(&self.image().image, size));
+        }
+        if self.stats.is_some() {
+            self.stats = Some(compute_stats(&self.image().image));
+        }
+    }
+
     fn display_message(&mut self, msg: String) {
         let now = Instant::now();
         self.footer_msg = Some(msg);
@@ -222,30 +1586,587 @@ impl App {
     }
 }
 
+/* Centered table of KEYBINDINGS on top of `area`, starting `scroll` rows down */
+fn render_help(area: Rect, buf: &mut Buffer, scroll: usize) {
+    let width = 64.min(area.width);
+    let height = (KEYBINDINGS.len() as u16 + 2).min(area.height);
+    let overlay = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+    let rows = KEYBINDINGS
+        .iter()
+        .skip(scroll)
+        .map(|&(key, action)| Row::new([key, action]));
+    let table = Table::new(rows, [Length(16), Min(0)]).block(
+        Block::bordered()
+            .title(" Keybindings (j/k to scroll) ")
+            .border_style(Style::new().fg(Color::Yellow)),
+    );
+    Clear.render(overlay, buf);
+    table.render(overlay, buf);
+}
+
 fn main() -> Result<()> {
-    println!("Running ratatui application!");
     color_eyre::install()?;
 
-    let args = parse_args();
+    let mut args = parse_args();
+    /* Exporting from Photos shows its own progress, which has to happen before the terminal is
+     * taken over */
+    args.paths = args
+        .paths
+        .iter()
+        .map(|path| resolve_path(path))
+        .collect::<Result<_>>()?;
+    /* Has to happen before ratatui switches to raw mode, since it talks to the terminal */
+    let capabilities = detect_capabilities();
+    if args.debug_caps {
+        println!("{:#?}", capabilities);
+        return Ok(());
+    }
 
-    let terminal = ratatui::init();
-    let app = App::new(args.path, terminal);
+    let config = args
+        .computed
+        .settings
+        .clone()
+        .or(Config::load(args.config.as_deref())?)
+        .with_defaults(capabilities.best_renderer());
+    if args.print_config {
+        print!("{}", toml::to_string(&config)?);
+        return Ok(());
+    }
+    if let Some(frames) = args.benchmark {
+        return run_benchmark(&args, &config, frames);
+    }
+    if let Some(size) = args.palette {
+        return print_palette(&args, size);
+    }
+    if args.exif_dump {
+        return print_exif(&expand_paths(&args, &mpsc::channel().0)?[0]);
+    }
+    if let Some(dir) = &args.batch_rename_exif {
+        return batch::rename_by_date(dir, args.dry_run);
+    }
+    if let (true, Some(input_dir), Some(output_dir)) =
+        (args.headless, &args.input_dir, &args.output_dir)
+    {
+        let mut opts = config.ascii_options();
+        opts.max_height = args.max_height;
+        opts.crop = args.computed.crop;
+        opts.resample = args.filter.unwrap_or(opts.resample);
+        let failed = batch::convert_dir(
+            input_dir,
+            output_dir,
+            &args.ext,
+            &args.computed.filters,
+            &opts,
+            args.error_log.as_deref(),
+        )?;
+        /* Scripts can tell something went wrong without parsing the summary */
+        if failed > 0 {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(path) = &args.resize_output {
+        return resize_output(&args, &config, path);
+    }
+    if args.output_format.is_some()
+        || args.format.unwrap_or_default() != DataFormat::Text
+        || args.output.is_some()
+        || args.fit_terminal
+    {
+        return export_ascii(&args, &config);
+    }
+
+    /* Before loading, which can take a while, so an early signal doesn't end the program */
+    let signals = Signals::register()?;
+
+    println!("Running ratatui application!");
+    let mut terminal = ratatui::init();
+    let images = match load_with_progress(&args, &mut terminal) {
+        Result::Ok(images) => images,
+        Err(e) => {
+            ratatui::restore();
+            return Err(e);
+        }
+    };
+    let mut watchers = Vec::new();
+    if args.watch {
+        /* Generated and piped images have no file to watch */
+        for image in images
+            .iter()
+            .filter(|image| Path::new(&image.path).is_file())
+        {
+            match FileWatcher::new(Path::new(&image.path)) {
+                Result::Ok(watcher) => watchers.push(watcher),
+                Err(e) => {
+                    ratatui::restore();
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+    let onion = match args.onion_skin.as_ref().map(::image::open).transpose() {
+        Result::Ok(onion) => onion,
+        Err(e) => {
+            ratatui::restore();
+            return Err(e.into());
+        }
+    };
+    let mut app = App::new(images, terminal, capabilities);
+    for image in &mut app.images {
+        image.renderer = match image.life {
+            /* Cells are square pixels, which only the half blocks keep square */
+            Some(_) => Renderer::HalfBlock,
+            None => config.renderer.unwrap_or(image.renderer),
+        };
+        image.color_depth = args.color_depth.unwrap_or(image.color_depth);
+        image.frame_blend = args.frame_blend.unwrap_or(image.frame_blend);
+        if let Some(vibrance) = args.vibrance {
+            image.set_vibrance(vibrance);
+        }
+        if let Some(grain) = args.grain {
+            image.set_grain(grain);
+        }
+        if let Some(probability) = args.computed.glitch {
+            image.set_glitch(probability);
+        }
+        image.set_split_view(args.split_view.clone());
+        image.set_mirror(args.computed.mirror);
+        if let Some(view) = image.fractal {
+            app.fractal_render = Some(fractal::render_in_background(view));
+        }
+    }
+    app.fps = args.fps.unwrap_or(DEFAULT_FPS);
+    app.open_options = args.open_options();
+    app.watermark = args.computed.watermark.clone();
+    app.rotate_exif = args.rotate_exif;
+    app.keybindings = config.key_commands();
+    app.sort_axis = args.sort_axis.unwrap_or_default();
+    app.watchers = watchers;
+    app.signals = signals;
+    if let Some(delay) = args.slideshow {
+        app.start_slideshow(delay);
+    }
+    let zoom_to_cursor = args.zoom_to_cursor;
+    app.startup = Some(Startup { args, onion });
+    /* The others are prepared when they are first shown */
+    for i in 0..app.images.len() {
+        if app.images[i].is_loaded() {
+            app.prepare_image(i);
+        }
+    }
+    if zoom_to_cursor {
+        std::io::stdout().execute(EnableMouseCapture)?;
+        app.zoom_to_cursor = true;
+    }
     let result = app.run();
+    if zoom_to_cursor {
+        std::io::stdout().execute(DisableMouseCapture)?;
+    }
 
     /* Call ratatui's restore function just for safety */
     ratatui::restore();
     result
 }
 
+/* The command line and the --onion-skin image, kept for the tabs decoded later */
+#[derive(Debug)]
+struct Startup {
+    args: CliArgs,
+    onion: Option<DynamicImage>,
+}
+
+/* What the command line does to every image that needs its pixels, run once it is decoded.
+ * Returns a message for the status bar if something didn't work */
+fn prepare_image(
+    image: &mut Image,
+    args: &CliArgs,
+    onion: Option<&DynamicImage>,
+) -> Option<String> {
+    if let Some(background) = args.computed.alpha_background {
+        image.alpha_background = Some(background);
+    }
+    image.set_onion_skin(onion.cloned());
+    if let Some(&[x, y, w, h]) = args.focus_region.as_deref() {
+        image.focus((x, y, w, h));
+    }
+    if args.center_of_mass {
+        image.center_on_mass();
+    }
+    /* Filters from the command line apply to every image */
+    for filter in &args.computed.filters {
+        image.apply_filter(filter);
+    }
+    /* Still images have nothing to seek in, they are left alone. The terminal is already taken
+     * over, so an error goes to the status bar and the whole animation plays */
+    if (args.start_frame.is_some() || args.end_frame.is_some()) && image.frames.len() > 1 {
+        return image
+            .set_frame_range(args.start_frame.unwrap_or(0), args.end_frame)
+            .err();
+    }
+    None
+}
+
+/* Turns special URLs into a local file path, regular paths are passed through */
+fn resolve_path(path: &str) -> Result<String> {
+    if !path.starts_with("photoslibrary://") {
+        return Ok(path.to_string());
+    }
+    #[cfg(feature = "macos-photos")]
+    {
+        Ok(photos::export_asset(path)?.to_string_lossy().into_owned())
+    }
+    #[cfg(not(feature = "macos-photos"))]
+    {
+        color_eyre::eyre::bail!("charcoal was built without the macos-photos feature")
+    }
+}
+
+/* How far the loader got, shown while the terminal has nothing else to show */
+enum Progress {
+    /* Files looked at while listing directories */
+    Scanned(usize),
+    /* Images whose EXIF date was read for --sort-by-exif-date, and how many there are */
+    Dated(usize, usize),
+}
+
+/* Progress is only sent every so many files, drawing it for each one would take longer than
+ * listing them */
+const PROGRESS_EVERY: usize = 100;
+
+/* The paths given on the command line, with directories replaced by the images in them */
+fn expand_paths(args: &CliArgs, progress: &Sender<Progress>) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    let mut scanned = 0;
+    for path in &args.paths {
+        if Path::new(path).is_dir() {
+            paths.extend(images_in(Path::new(path), args, &mut scanned, progress)?);
+        } else {
+            paths.push(path.clone());
+        }
+    }
+    Ok(paths)
+}
+
+/* Files in `dir` with an image extension, sorted by name. `scanned` counts every file looked at
+ * across directories */
+fn images_in(
+    dir: &Path,
+    args: &CliArgs,
+    scanned: &mut usize,
+    progress: &Sender<Progress>,
+) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        *scanned += 1;
+        if scanned.is_multiple_of(PROGRESS_EVERY) {
+            /* Nobody listens once drawing the status bar failed */
+            let _ = progress.send(Progress::Scanned(*scanned));
+        }
+        let Result::Ok(path) = entry.map(|entry| entry.path()) else {
+            continue;
+        };
+        if path.is_file() && animation::is_supported(&path) {
+            paths.push(path.to_string_lossy().into_owned());
+        }
+    }
+    if paths.is_empty() {
+        color_eyre::eyre::bail!("No images found in {}", dir.display());
+    }
+    paths.sort();
+    if args.sort_by_exif_date {
+        let total = paths.len();
+        let mut dated: Vec<_> = paths
+            .into_iter()
+            .enumerate()
+            .map(|(i, path)| {
+                if (i + 1).is_multiple_of(PROGRESS_EVERY) {
+                    let _ = progress.send(Progress::Dated(i + 1, total));
+                }
+                let date = exif::read(Path::new(&path))
+                    .ok()
+                    .and_then(|exif| exif::date_taken(&exif));
+                ((date.is_some() == args.undated_first, date), path)
+            })
+            .collect();
+        /* Stable, so photos taken in the same second and undated ones stay sorted by name */
+        dated.sort_by(|(a, _), (b, _)| a.cmp(b));
+        paths = dated.into_iter().map(|(_, path)| path).collect();
+    }
+    Ok(paths)
+}
+
+/* Lists directories on another thread and shows how far it got meanwhile, so a directory of
+ * thousands of files doesn't look like the program hangs */
+fn load_with_progress(args: &CliArgs, terminal: &mut DefaultTerminal) -> Result<Vec<Image>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        let loader = scope.spawn(move || load_images(args, &sender));
+        let mut draw = |status: String| {
+            terminal
+                .draw(|f| {
+                    Text::from(status)
+                        .centered()
+                        .render(f.area(), f.buffer_mut());
+                })
+                .map(|_| ())
+        };
+        draw("Scanning...".to_string())?;
+        /* Ends when the loader is done and drops the sender */
+        for progress in receiver {
+            draw(match progress {
+                Progress::Scanned(files) => format!("Scanning: {} files", files),
+                Progress::Dated(done, total) => format!("Reading dates: {}/{} images", done, total),
+            })?;
+        }
+        loader.join().expect("the image loader panicked")
+    })
+}
+
+/* The images given on the command line, or one generated from the other flags. Only the first
+ * one and piped ones are decoded here, the other tabs when they are first shown */
+fn load_images(args: &CliArgs, progress: &Sender<Progress>) -> Result<Vec<Image>> {
+    /* Starts out black, the viewer renders the fractal in the background */
+    if let Some(view) = args.computed.fractal {
+        let blank = DynamicImage::new_rgb8(view.width, view.height);
+        let mut image = Image::from_dynamic(view.kind.name().to_string(), blank);
+        image.fractal = Some(view);
+        return Ok(vec![image]);
+    }
+    if args.preview_font.is_some()
+        || args.computed.plasma.is_some()
+        || args.life.is_some()
+        || args.computed.chessboard.is_some()
+        || args.computed.noise.is_some()
+        || args.average.is_some()
+        || args.stdev.is_some()
+    {
+        return Ok(vec![load_image(args)?]);
+    }
+    let mut images = Vec::new();
+    for (i, path) in expand_paths(args, progress)?.into_iter().enumerate() {
+        images.push(if i == 0 || path == STDIN_PATH {
+            open_image(&path, args.open_options())?
+        } else {
+            Image::unloaded(path, args.open_options())
+        });
+    }
+    Ok(images)
+}
+
+/* Opens the file at `path`, or reads the image from stdin if `path` is `-`. Piped images don't
+ * need to be seekable, they are read into memory first */
+fn open_image(path: &str, options: OpenOptions) -> Result<Image> {
+    if path != STDIN_PATH {
+        return Ok(Image::open(path.to_string(), options)?);
+    }
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        color_eyre::eyre::bail!("No image was piped to stdin");
+    }
+    let mut data = Vec::new();
+    stdin.read_to_end(&mut data)?;
+    let image = ::image::load_from_memory(&data)?;
+    Ok(Image::from_dynamic("stdin".to_string(), image))
+}
+
+/* The batch modes only work on a single image, the first one */
+fn load_image(args: &CliArgs) -> Result<Image> {
+    if let Some(font) = &args.preview_font {
+        let specimen = font_preview::render_specimen(font)?;
+        return Ok(Image::from_dynamic(font.display().to_string(), specimen));
+    }
+    if let Some(view) = &args.computed.fractal {
+        return Ok(Image::from_dynamic(
+            view.kind.name().to_string(),
+            fractal::render(view),
+        ));
+    }
+    if let Some(board) = &args.computed.chessboard {
+        return Ok(Image::from_dynamic(
+            "chessboard".to_string(),
+            board.render(),
+        ));
+    }
+    if let Some((width, height)) = args.life {
+        let life = Life::random(width, height);
+        let mut image = Image::from_dynamic("life".to_string(), life.image());
+        image.life = Some(life);
+        return Ok(image);
+    }
+    if let Some((width, height)) = args.computed.plasma {
+        let frames = plasma::frames(width, height);
+        let mut image = Image::from_dynamic("plasma".to_string(), frames[0].0.clone());
+        image.frames = frames;
+        return Ok(image);
+    }
+    if let Some(settings) = &args.computed.noise {
+        let image = noise::generate(settings, ::rand::random());
+        return Ok(Image::from_dynamic(settings.kind.name().to_string(), image));
+    }
+    if let Some(dir) = &args.average {
+        let mean = stack::average(dir)?;
+        return Ok(Image::from_dynamic(dir.display().to_string(), mean));
+    }
+    if let Some(dir) = &args.stdev {
+        let deviation = stack::std_dev(dir)?;
+        return Ok(Image::from_dynamic(dir.display().to_string(), deviation));
+    }
+    open_image(
+        &expand_paths(args, &mpsc::channel().0)?[0],
+        args.open_options(),
+    )
+}
+
+/* Non-interactive path: convert the image to ASCII art and write it out */
+fn export_ascii(args: &CliArgs, config: &Config) -> Result<()> {
+    let mut image = load_image(args)?;
+    for filter in &args.computed.filters {
+        image.apply_filter(filter);
+    }
+    let mut opts = config.ascii_options();
+    opts.max_height = args.max_height;
+    opts.format = args.output_format.unwrap_or_default();
+    opts.crop = args.computed.crop;
+    opts.resample = args.filter.unwrap_or(opts.resample);
+    if args.fit_terminal {
+        /* Keep one line free for the shell prompt */
+        let (cols, rows) = ratatui::crossterm::terminal::size()?;
+        opts.max_width = cols as u32;
+        opts.max_height = Some(rows.saturating_sub(1) as u32);
+    }
+    let mut grid = ascii::ascii_grid(&image.image, &opts);
+    if let Some((text, position)) = &args.computed.watermark {
+        watermark::stamp(&mut grid, text, *position);
+    }
+    let ascii = match args.format.unwrap_or_default() {
+        DataFormat::Text => ascii::grid_to_ascii(&grid, opts.format),
+        format => {
+            /* Any of the colored formats adds the colors to the data */
+            let color = opts.format != OutputFormat::Plain;
+            let mut out = Vec::new();
+            match (format, color) {
+                (DataFormat::Csv, _) => write_csv(&mut out, &grid, color)?,
+                (_, false) => JsonWriter::new(&mut out).write_mono(&grid)?,
+                (_, true) => JsonWriter::new(&mut out).write_color(&grid)?,
+            }
+            String::from_utf8(out)?
+        }
+    };
+    ascii::write_ascii_output(&ascii, args.output.as_deref())?;
+    Ok(())
+}
+
+/* Crops and scales the image like the ASCII export, but saves it as an image. The format is
+ * picked by the extension of `path` */
+fn resize_output(args: &CliArgs, config: &Config, path: &Path) -> Result<()> {
+    let mut image = load_image(args)?;
+    for filter in &args.computed.filters {
+        image.apply_filter(filter);
+    }
+    let mut opts = config.ascii_options();
+    opts.max_height = args.max_height;
+    opts.crop = args.computed.crop;
+    opts.resample = args.filter.unwrap_or(opts.resample);
+    /* Pixels are square, unlike terminal cells */
+    opts.char_aspect = 1.0;
+    let pixels = match args.rotate_exif {
+        true => image.upright(),
+        false => Cow::Borrowed(&image.image),
+    };
+    let resized = ascii::resize_image(&pixels, &opts, args.resize_width, args.resize_height);
+    save_image(&resized, path)
+}
+
+/* Encodes the pixels anew in the format picked by the extension of `path`. Nothing of the
+ * original file is copied, so EXIF, ICC profiles and other metadata are always left out */
+fn save_image(image: &DynamicImage, path: &Path) -> Result<()> {
+    /* JPEG has no alpha channel, and most filters return RGBA */
+    match ::image::ImageFormat::from_path(path) {
+        Result::Ok(::image::ImageFormat::Jpeg) => {
+            DynamicImage::ImageRgb8(image.to_rgb8()).save(path)?
+        }
+        _ => image.save(path)?,
+    }
+    Ok(())
+}
+
+/* Prints the hex codes of the `size` most common colors, one per line */
+fn print_palette(args: &CliArgs, size: usize) -> Result<()> {
+    let mut image = load_image(args)?;
+    for filter in &args.computed.filters {
+        image.apply_filter(filter);
+    }
+    for color in palette::median_cut(&image.image, size) {
+        println!("{}", palette::hex(color));
+    }
+    Ok(())
+}
+
+fn print_exif(path: &str) -> Result<()> {
+    let exif = exif::read(Path::new(path))?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&exif::to_json_object(&exif))?
+    );
+    Ok(())
+}
+
+/* Renders the image into an off-screen buffer `frames` times and reports the timings. The
+ * terminal is never put into raw mode, so this can run in CI */
+fn run_benchmark(args: &CliArgs, config: &Config, frames: usize) -> Result<()> {
+    let mut image = load_image(args)?;
+    image.renderer = config.renderer.unwrap_or_default();
+    image.color_depth = args.color_depth.unwrap_or_default();
+    for filter in &args.computed.filters {
+        image.apply_filter(filter);
+    }
+    let (cols, rows) = ratatui::crossterm::terminal::size().unwrap_or((120, 40));
+    let area = Rect::new(0, 0, cols, rows);
+    let mut buf = Buffer::empty(area);
+
+    let mut times_ms: Vec<f64> = Vec::with_capacity(frames);
+    let start = Instant::now();
+    for _ in 0..frames {
+        let frame_start = Instant::now();
+        image.render(area, &mut buf);
+        times_ms.push(frame_start.elapsed().as_secs_f64() * 1000.0);
+    }
+    let total = start.elapsed();
+
+    let n = times_ms.len().max(1) as f64;
+    let mean = times_ms.iter().sum::<f64>() / n;
+    let std_dev = (times_ms.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / n).sqrt();
+    times_ms.sort_by(f64::total_cmp);
+    let p99 = times_ms
+        .get(((times_ms.len() as f64 * 0.99).ceil() as usize).saturating_sub(1))
+        .copied()
+        .unwrap_or(0.0);
+
+    println!(
+        "Rendered {} frames at {}x{} with {:?} in {:.1?} (mean {:.2} ms, std dev {:.2} ms)",
+        frames, cols, rows, image.color_depth, total, mean, std_dev
+    );
+    println!(
+        "charcoal_bench frames={} mean_ms={:.1} p99_ms={:.1}",
+        frames, mean, p99
+    );
+    Ok(())
+}
+
 impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let [top, main] = Layout::vertical([Length(1), Min(0)]).areas(area);
         let [title, _] = Layout::horizontal([Min(0), Length(8)]).areas(top);
-        Text::from(format!("File: {}", self.image.path.clone(),))
+        Text::from(format!("File: {}", self.image().path.clone(),))
             .centered()
             .render(title, buf);
         /* Function, which actually writes stuff to the given buffer */
-        self.image.render(main, buf);
+        self.image_mut().render(main, buf);
 
         Text::from(format!("\n Command Buffer{}", self.command_buffer.clone()))
             .left_aligned()
@@ -256,7 +2177,7 @@ impl Widget for &mut App {
                 .left_aligned()
                 .render(title, buf);
         } else {
-            Text::from(format!("Press ':' to enable command mode or q to exit!"))
+            Text::from("Press ':' to enable command mode or q to exit!")
                 .left_aligned()
                 .render(title, buf);
         }
@@ -264,17 +2185,134 @@ impl Widget for &mut App {
 }
 
 fn parse_args() -> CliArgs {
-    let args: Vec<String> = env::args().skip(1).collect();
-    if args.len() != 1 {
-        print_usage();
-        std::process::exit(1);
+    let cli::Parsed {
+        mut args,
+        mut filters,
+        paths,
+        crop,
+        mirror,
+        fractal: fractal_kind,
+        drop_shadow,
+    } = cli::parse();
+
+    /* The other filters shouldn't see the borders, a vignette would darken them instead of the
+     * picture */
+    if args.auto_crop_whitespace {
+        let threshold = args.crop_threshold.unwrap_or(filters::AUTO_CROP_THRESHOLD);
+        filters.insert(0, Filter::AutoCrop { threshold });
     }
 
-    CliArgs {
-        path: args[0].clone(),
+    /* Runs after the other filters, so it sees the image they produced */
+    if let Some(threshold) = &args.pixel_sort_threshold {
+        filters.push(Filter::SortPixels {
+            key: SortKey::Luma,
+            axis: args.sort_axis.unwrap_or_default(),
+            threshold: Some((threshold[0], threshold[1])),
+        });
     }
+    if let Some(dir) = &args.mosaic_tile {
+        let library = TileLibrary::load(dir, args.tile_px.unwrap_or(16))
+            .unwrap_or_else(|e| exit_with_error(&e));
+        filters.push(Filter::Mosaic(Arc::new(library)));
+    }
+    /* After the other filters, so the flat colors aren't smoothed over again */
+    filters.extend(args.posterize.clone());
+    if args.pad_to_square {
+        filters.push(Filter::PadToSquare {
+            background: args
+                .pad_color
+                .map_or([0; 4], |::image::Rgb([r, g, b])| [r, g, b, 255]),
+            gravity: args.pad_gravity.unwrap_or_default(),
+        });
+    }
+    filters.extend(args.round_corners.clone());
+    filters.extend(drop_shadow);
+
+    let defaults = FractalView::default();
+    let fractal_view = FractalView {
+        zoom: args.mandelbrot_zoom.unwrap_or(defaults.zoom),
+        width: args.width.unwrap_or(defaults.width),
+        height: args.height.unwrap_or(defaults.height),
+        ..defaults
+    };
+
+    /* The path doesn't matter when only printing diagnostics */
+    if paths.is_empty()
+        && !(args.debug_caps
+            || args.print_config
+            || args.preview_font.is_some()
+            || args.average.is_some()
+            || args.stdev.is_some()
+            || fractal_kind.is_some()
+            || args.plasma
+            || args.life.is_some()
+            || args.chessboard.is_some()
+            || args.noise.is_some()
+            || args.headless
+            || args.batch_rename_exif.is_some())
+    {
+        exit_with_usage()
+    }
+    args.paths = paths;
+
+    args.computed = Computed {
+        filters,
+        crop,
+        mirror,
+        glitch: args
+            .glitch_probability
+            .or(args.glitch.then_some(DEFAULT_GLITCH_PROBABILITY)),
+        /* The cut off corners blend into the usual dark terminal instead of a checkerboard */
+        alpha_background: args
+            .alpha_bg
+            .or(args.round_corners.as_ref().map(|_| AlphaBackground::Black)),
+        watermark: args
+            .watermark
+            .clone()
+            .map(|text| (text, args.watermark_position.unwrap_or_default())),
+        settings: Config {
+            max_width: args.max_width,
+            char_aspect: args.char_aspect,
+            charset: args.charset.clone(),
+            renderer: args.renderer,
+            ..Config::default()
+        },
+        fractal: fractal_kind.map(|kind| FractalView {
+            kind,
+            center: args.mandelbrot_center.unwrap_or(kind.default_center()),
+            ..fractal_view
+        }),
+        chessboard: args.chessboard.map(|squares| Chessboard {
+            squares,
+            width: args.width.unwrap_or(chessboard::DEFAULT_SIZE.0),
+            height: args.height.unwrap_or(chessboard::DEFAULT_SIZE.1),
+            colors: (
+                args.cb_color1.unwrap_or(chessboard::DEFAULT_COLORS.0),
+                args.cb_color2.unwrap_or(chessboard::DEFAULT_COLORS.1),
+            ),
+        }),
+        plasma: args.plasma.then(|| {
+            (
+                args.width.unwrap_or(plasma::DEFAULT_SIZE.0),
+                args.height.unwrap_or(plasma::DEFAULT_SIZE.1),
+            )
+        }),
+        noise: args.noise.map(|kind| NoiseSettings {
+            kind,
+            octaves: args.noise_octaves.unwrap_or(1),
+            width: fractal_view.width,
+            height: fractal_view.height,
+        }),
+    };
+    args
+}
+
+fn exit_with_error(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    std::process::exit(1);
 }
 
-fn print_usage() {
-    println!("Usage: terminal-image-viewer path/to/file.png");
+fn exit_with_usage() -> ! {
+    cli::print_help();
+    std::process::exit(1);
 }
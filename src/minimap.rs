@@ -0,0 +1,46 @@
+/* A thumbnail of the whole image with an outline around the part that is on screen */
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Block, Widget},
+};
+
+use crate::image::Image;
+
+const WIDTH: u16 = 20;
+const HEIGHT: u16 = 10;
+
+pub struct Minimap<'a>(pub &'a Image);
+
+impl Widget for Minimap<'_> {
+    /* Draws into the bottom-right corner of `area` */
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let image = self.0;
+        let (img_w, img_h) = (image.displayed().width(), image.displayed().height());
+        let width = WIDTH.min(area.width);
+        let height = HEIGHT.min(area.height);
+        if width < 2 || height < 2 || img_w == 0 || img_h == 0 {
+            return;
+        }
+        let map = Rect::new(area.right() - width, area.bottom() - height, width, height);
+        let thumb = image.render_region(map, buf, (0, 0, img_w, img_h));
+
+        /* Scale the visible region down to thumbnail cells. The outline needs 2x2 cells,
+         * anything smaller would only draw a single corner */
+        let (x, y, w, h) = image.visible_region();
+        let scale = |v: u32, cells: u16, full: u32| (v as u64 * cells as u64 / full as u64) as u16;
+        let view_x = thumb.x + scale(x, thumb.width, img_w);
+        let view_y = thumb.y + scale(y, thumb.height, img_h);
+        let view_w = scale(w, thumb.width, img_w)
+            .max(2)
+            .min(thumb.right() - view_x);
+        let view_h = scale(h, thumb.height, img_h)
+            .max(2)
+            .min(thumb.bottom() - view_y);
+        let viewport = Rect::new(view_x, view_y, view_w, view_h).intersection(thumb);
+        Block::bordered()
+            .border_style(Style::new().fg(Color::LightYellow))
+            .render(viewport, buf);
+    }
+}
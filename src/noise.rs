@@ -0,0 +1,85 @@
+/* Procedural noise rendered as a grayscale image, e.g. to use as a texture */
+use ::noise::{Fbm, MultiFractal, NoiseFn, Perlin, Simplex};
+use image::{DynamicImage, GrayImage, Luma};
+use rand::{RngExt, SeedableRng, rngs::StdRng};
+
+/* How many noise cells fit across the width, more makes the features smaller */
+const SCALE: f64 = 8.0;
+/* fBm gets too fine to see at all past this, and the noise crate caps it anyway */
+pub const MAX_OCTAVES: usize = Fbm::<Perlin>::MAX_OCTAVES;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseType {
+    Perlin,
+    Simplex,
+    /* Every pixel independent of its neighbours, octaves don't change it */
+    White,
+}
+
+impl NoiseType {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "perlin" => Ok(NoiseType::Perlin),
+            "simplex" => Ok(NoiseType::Simplex),
+            "white" => Ok(NoiseType::White),
+            _ => Err(format!(
+                "Unknown noise type: {} (expected perlin, simplex or white)",
+                s
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoiseType::Perlin => "perlin",
+            NoiseType::Simplex => "simplex",
+            NoiseType::White => "white",
+        }
+    }
+}
+
+/* What to generate, and the size of the image it's drawn into */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseSettings {
+    pub kind: NoiseType,
+    pub octaves: usize,
+    pub width: u32,
+    pub height: u32,
+}
+
+/* Samples `noise` at every pixel, which gives values of roughly -1 to 1 */
+fn sample(noise: &impl NoiseFn<f64, 2>, width: u32, height: u32) -> GrayImage {
+    let scale = SCALE / width as f64;
+    GrayImage::from_fn(width, height, |x, y| {
+        let v = noise.get([x as f64 * scale, y as f64 * scale]);
+        Luma([((v + 1.0) / 2.0 * 255.0).round().clamp(0.0, 255.0) as u8])
+    })
+}
+
+/// An image of the noise in `settings`. More than one octave layers finer copies on top as
+/// fractal detail. The same `seed` always gives the same image
+pub fn generate(settings: &NoiseSettings, seed: u32) -> DynamicImage {
+    let NoiseSettings {
+        kind,
+        octaves,
+        width,
+        height,
+    } = *settings;
+    let img = match kind {
+        NoiseType::Perlin => sample(
+            &Fbm::<Perlin>::new(seed).set_octaves(octaves),
+            width,
+            height,
+        ),
+        NoiseType::Simplex => sample(
+            &Fbm::<Simplex>::new(seed).set_octaves(octaves),
+            width,
+            height,
+        ),
+        NoiseType::White => {
+            let mut rng = StdRng::seed_from_u64(seed as u64);
+            GrayImage::from_fn(width, height, |_, _| Luma([rng.random()]))
+        }
+    };
+    DynamicImage::ImageLuma8(img)
+}
@@ -0,0 +1,140 @@
+/* Documents with several pages, multi-page TIFFs and (with the pdf feature) PDFs. Unlike the
+ * frames of an animation these don't advance on their own, they're flipped through by hand */
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use image::{
+    DynamicImage, ImageBuffer, ImageError, ImageResult,
+    error::{DecodingError, ImageFormatHint},
+};
+use tiff::{
+    ColorType,
+    decoder::{Decoder, DecodingResult},
+};
+
+fn magic(path: &Path) -> [u8; 5] {
+    let mut header = [0u8; 5];
+    if let Ok(mut file) = File::open(path) {
+        let _ = file.read(&mut header);
+    }
+    header
+}
+
+/// Every page of the document at `path`, if it's a format with pages. Single page TIFFs return
+/// None as well so they are decoded like any other image
+pub fn decode(path: &Path) -> ImageResult<Option<Vec<DynamicImage>>> {
+    let header = magic(path);
+    if &header == b"%PDF-" {
+        #[cfg(feature = "pdf")]
+        return pdf(path).map(Some);
+        #[cfg(not(feature = "pdf"))]
+        return Err(crate::animation::not_compiled("PDF", "pdf"));
+    }
+    if &header[..4] == b"II*\0" || &header[..4] == b"MM\0*" {
+        /* Layouts the conversion below doesn't handle are left to the image crate */
+        return Ok(tiff_pages(path).ok().filter(|pages| pages.len() > 1));
+    }
+    Ok(None)
+}
+
+fn tiff_error(err: tiff::TiffError) -> ImageError {
+    ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Name("TIFF".to_string()),
+        err,
+    ))
+}
+
+fn tiff_pages(path: &Path) -> ImageResult<Vec<DynamicImage>> {
+    let mut decoder = Decoder::new(BufReader::new(File::open(path)?)).map_err(tiff_error)?;
+    let mut pages = Vec::new();
+    loop {
+        let (width, height) = decoder.dimensions().map_err(tiff_error)?;
+        let color = decoder.colortype().map_err(tiff_error)?;
+        let data = decoder.read_image().map_err(tiff_error)?;
+        pages.push(to_dynamic(width, height, color, data).ok_or_else(|| {
+            tiff_error(tiff::TiffError::UnsupportedError(
+                tiff::TiffUnsupportedError::UnsupportedColorType(color),
+            ))
+        })?);
+        if !decoder.more_images() {
+            return Ok(pages);
+        }
+        decoder.next_image().map_err(tiff_error)?;
+    }
+}
+
+fn to_dynamic(
+    width: u32,
+    height: u32,
+    color: ColorType,
+    data: DecodingResult,
+) -> Option<DynamicImage> {
+    Some(match (color, data) {
+        (ColorType::Gray(8), DecodingResult::U8(data)) => {
+            DynamicImage::ImageLuma8(ImageBuffer::from_raw(width, height, data)?)
+        }
+        (ColorType::GrayA(8), DecodingResult::U8(data)) => {
+            DynamicImage::ImageLumaA8(ImageBuffer::from_raw(width, height, data)?)
+        }
+        (ColorType::RGB(8), DecodingResult::U8(data)) => {
+            DynamicImage::ImageRgb8(ImageBuffer::from_raw(width, height, data)?)
+        }
+        (ColorType::RGBA(8), DecodingResult::U8(data)) => {
+            DynamicImage::ImageRgba8(ImageBuffer::from_raw(width, height, data)?)
+        }
+        (ColorType::Gray(16), DecodingResult::U16(data)) => {
+            DynamicImage::ImageLuma16(ImageBuffer::from_raw(width, height, data)?)
+        }
+        (ColorType::GrayA(16), DecodingResult::U16(data)) => {
+            DynamicImage::ImageLumaA16(ImageBuffer::from_raw(width, height, data)?)
+        }
+        (ColorType::RGB(16), DecodingResult::U16(data)) => {
+            DynamicImage::ImageRgb16(ImageBuffer::from_raw(width, height, data)?)
+        }
+        (ColorType::RGBA(16), DecodingResult::U16(data)) => {
+            DynamicImage::ImageRgba16(ImageBuffer::from_raw(width, height, data)?)
+        }
+        _ => return None,
+    })
+}
+
+/* PDF pages are rendered at this resolution, their size is given in points (1/72 inch) */
+#[cfg(feature = "pdf")]
+const PDF_DPI: f32 = 150.0;
+
+/* pdfium is loaded at runtime, so this only fails once a PDF is actually opened */
+#[cfg(feature = "pdf")]
+fn pdf(path: &Path) -> ImageResult<Vec<DynamicImage>> {
+    use pdfium_render::prelude::{PdfRenderConfig, Pdfium, PdfiumError};
+
+    let pdf_error = |err: PdfiumError| {
+        ImageError::Decoding(DecodingError::new(
+            ImageFormatHint::Name("PDF".to_string()),
+            err,
+        ))
+    };
+    let pdfium = Pdfium::new(Pdfium::bind_to_system_library().map_err(pdf_error)?);
+    let document = pdfium.load_pdf_from_file(path, None).map_err(pdf_error)?;
+    let config = PdfRenderConfig::new().scale_page_by_factor(PDF_DPI / 72.0);
+    let pages = document
+        .pages()
+        .iter()
+        .map(|page| {
+            Ok(page
+                .render_with_config(&config)
+                .map_err(pdf_error)?
+                .as_image())
+        })
+        .collect::<ImageResult<Vec<_>>>()?;
+    /* The viewer always shows the first page, a document without any has nothing to show */
+    if pages.is_empty() {
+        return Err(ImageError::Decoding(DecodingError::new(
+            ImageFormatHint::Name("PDF".to_string()),
+            "the document has no pages",
+        )));
+    }
+    Ok(pages)
+}
@@ -0,0 +1,125 @@
+/* The most representative colors of an image, found with median cut: the pixels are split into
+ * boxes along the channel they differ most in until there are enough boxes, then every box is
+ * averaged into one color */
+use image::{DynamicImage, GenericImageView, Rgb};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+/* Larger images are sampled, the palette hardly changes but it's a lot faster */
+const MAX_SAMPLES: u64 = 100_000;
+/* Cells per color, the square takes 3 and the hex code below it 7 */
+const SLOT_WIDTH: u16 = 8;
+const SQUARE_WIDTH: u16 = 3;
+
+#[derive(Debug)]
+pub struct Palette {
+    /// Number of colors asked for, there may be fewer if the image has fewer
+    pub size: usize,
+    /// Ordered by how many pixels they stand for, most first
+    pub colors: Vec<Rgb<u8>>,
+}
+
+impl Palette {
+    pub fn new(img: &DynamicImage, size: usize) -> Self {
+        Self {
+            size,
+            colors: median_cut(img, size),
+        }
+    }
+
+    /// Rows taken up at the bottom of the area it's drawn into
+    pub const HEIGHT: u16 = 2;
+}
+
+/* Index of the channel with the largest spread in `pixels`, and that spread */
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|c| {
+            let (min, max) = pixels
+                .iter()
+                .fold((255, 0), |(min, max), p| (p[c].min(min), p[c].max(max)));
+            (c, max.saturating_sub(min))
+        })
+        .max_by_key(|&(_, spread)| spread)
+        .expect("there are three channels")
+}
+
+/// Up to `n` colors that together represent `img` best
+pub fn median_cut(img: &DynamicImage, n: usize) -> Vec<Rgb<u8>> {
+    let (width, height) = img.dimensions();
+    let step = (width as u64 * height as u64 / MAX_SAMPLES).max(1) as usize;
+    let pixels: Vec<[u8; 3]> = img
+        .pixels()
+        .step_by(step)
+        .map(|(_, _, p)| [p[0], p[1], p[2]])
+        .collect();
+    if pixels.is_empty() || n == 0 {
+        return Vec::new();
+    }
+
+    let mut boxes = vec![pixels];
+    while boxes.len() < n {
+        /* Split the box with the widest spread, a box of identical pixels can't be split */
+        let Some((i, channel)) = boxes
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (i, widest_channel(b)))
+            .filter(|&(_, (_, spread))| spread > 0)
+            .max_by_key(|&(_, (_, spread))| spread)
+            .map(|(i, (channel, _))| (i, channel))
+        else {
+            break;
+        };
+        let mut pixels = boxes.swap_remove(i);
+        pixels.sort_unstable_by_key(|p| p[channel]);
+        let upper = pixels.split_off(pixels.len() / 2);
+        boxes.push(pixels);
+        boxes.push(upper);
+    }
+
+    boxes.sort_by_key(|b| std::cmp::Reverse(b.len()));
+    boxes
+        .iter()
+        .map(|b| {
+            let sum = b.iter().fold([0u64; 3], |mut sum, p| {
+                for c in 0..3 {
+                    sum[c] += p[c] as u64;
+                }
+                sum
+            });
+            Rgb(sum.map(|s| (s / b.len() as u64) as u8))
+        })
+        .collect()
+}
+
+/// Formats a color as `#rrggbb`
+pub fn hex(color: Rgb<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+impl Widget for &Palette {
+    /* Draws a row of squares along the bottom-left of `area`, each with its hex code below */
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < Palette::HEIGHT {
+            return;
+        }
+        let y = area.bottom() - Palette::HEIGHT;
+        let fits = (area.width / SLOT_WIDTH) as usize;
+        for (i, &color) in self.colors.iter().take(fits).enumerate() {
+            let x = area.x + i as u16 * SLOT_WIDTH;
+            let [r, g, b] = color.0;
+            let square = Style::new().fg(Color::Rgb(r, g, b));
+            buf.set_string(x, y, "█".repeat(SQUARE_WIDTH as usize), square);
+            buf.set_string(
+                x,
+                y + 1,
+                hex(color),
+                Style::new().fg(Color::White).bg(Color::Black),
+            );
+        }
+    }
+}
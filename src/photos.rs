@@ -0,0 +1,76 @@
+/* Loading assets from the macOS Photos library, e.g. `photoslibrary://media/asset/id/<ID>`.
+ * Photos is asked via AppleScript to export the original into a temporary directory of its own,
+ * which is then opened like any other file */
+use std::{
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use color_eyre::eyre::{Result, bail, eyre};
+
+/// Exports the asset behind `url` and returns the path of the exported file
+pub fn export_asset(url: &str) -> Result<PathBuf> {
+    let id = url
+        .rsplit_once("/id/")
+        .map(|(_, id)| id.trim_end_matches('/'))
+        .filter(|id| !id.is_empty() && !id.contains('"'))
+        .ok_or_else(|| eyre!("Not a Photos asset URL: {}", url))?;
+
+    /* Every asset gets its own directory, so an earlier export can't be mistaken for this one */
+    let dir = std::env::temp_dir()
+        .join(format!("charcoal-photos-{}", std::process::id()))
+        .join(id.replace('/', "_"));
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    fs::create_dir_all(&dir)?;
+    let script = format!(
+        "tell application \"Photos\"\n\
+         export {{media item id \"{}\"}} to POSIX file \"{}\" with using originals\n\
+         end tell",
+        id,
+        dir.display()
+    );
+
+    let mut child = Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    /* Exporting can take a while for iCloud assets, so show that something is happening */
+    let spinner = ['|', '/', '-', '\\'];
+    let mut frame = 0;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        eprint!(
+            "\rExporting from Photos {} ",
+            spinner[frame % spinner.len()]
+        );
+        let _ = std::io::stderr().flush();
+        frame += 1;
+        thread::sleep(Duration::from_millis(100));
+    };
+    eprintln!("\rExporting from Photos done");
+
+    if !status.success() {
+        let mut err = String::new();
+        if let Some(mut stderr) = child.stderr.take() {
+            let _ = std::io::Read::read_to_string(&mut stderr, &mut err);
+        }
+        bail!("Photos export failed: {}", err.trim());
+    }
+
+    fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| path.is_file())
+        .ok_or_else(|| eyre!("Photos did not export anything for {}", url))
+}
@@ -0,0 +1,47 @@
+/* The classic demoscene plasma, a sum of sine waves that drift as time goes on */
+use std::{f32::consts::TAU, time::Duration};
+
+use image::{DynamicImage, Rgb, RgbImage};
+
+use crate::{animation::Frames, filters::hsv_to_rgb};
+
+/* Every wave has a period of TAU in t, so after FRAMES frames the animation loops seamlessly */
+const FRAMES: usize = 90;
+/* About 30 FPS */
+const FRAME_DELAY: Duration = Duration::from_millis(33);
+/* How many wave crests fit across the width, so the plasma looks the same at any size */
+const WAVES: f32 = 3.0;
+
+pub const DEFAULT_SIZE: (u32, u32) = (320, 240);
+
+/* The four waves added together, between -4 and 4 */
+fn value(x: f32, y: f32, t: f32, (cx, cy): (f32, f32)) -> f32 {
+    let radius = ((x - cx).powi(2) + (y - cy).powi(2)).sqrt();
+    (x + t).sin() + (y + t).sin() + ((x + y) / 2.0 + t).sin() + (radius - t).sin()
+}
+
+fn frame(width: u32, height: u32, t: f32) -> DynamicImage {
+    let frequency = TAU * WAVES / width as f32;
+    let center = (
+        width as f32 / 2.0 * frequency,
+        height as f32 / 2.0 * frequency,
+    );
+    let img = RgbImage::from_fn(width, height, |x, y| {
+        let v = value(x as f32 * frequency, y as f32 * frequency, t, center);
+        /* The whole palette also rotates once per loop */
+        let hue = (v + 4.0) / 8.0 * 360.0 + t.to_degrees();
+        let (r, g, b) = hsv_to_rgb(hue, 0.9, 1.0);
+        Rgb([r, g, b])
+    });
+    DynamicImage::ImageRgb8(img)
+}
+
+/// Every frame of one loop of the plasma, played back like any other animation
+pub fn frames(width: u32, height: u32) -> Frames {
+    (0..FRAMES)
+        .map(|i| {
+            let t = i as f32 / FRAMES as f32 * TAU;
+            (frame(width, height, t), FRAME_DELAY)
+        })
+        .collect()
+}
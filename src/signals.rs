@@ -0,0 +1,36 @@
+/* Lets other programs drive the viewer: SIGUSR1 reloads the current image and SIGUSR2 moves on
+ * to the next one. Windows has no such signals, so there neither flag is ever set */
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+#[derive(Debug, Default)]
+pub struct Signals {
+    reload: Arc<AtomicBool>,
+    next: Arc<AtomicBool>,
+}
+
+impl Signals {
+    /// Starts listening for SIGUSR1 and SIGUSR2
+    pub fn register() -> std::io::Result<Self> {
+        let signals = Signals::default();
+        #[cfg(unix)]
+        {
+            use signal_hook::{consts, flag};
+            flag::register(consts::SIGUSR1, Arc::clone(&signals.reload))?;
+            flag::register(consts::SIGUSR2, Arc::clone(&signals.next))?;
+        }
+        Ok(signals)
+    }
+
+    /// Whether SIGUSR1 arrived since the last call
+    pub fn take_reload(&self) -> bool {
+        self.reload.swap(false, Ordering::Relaxed)
+    }
+
+    /// Whether SIGUSR2 arrived since the last call
+    pub fn take_next(&self) -> bool {
+        self.next.swap(false, Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,121 @@
+/* Combining every image in a directory into one, pixel by pixel. Images of different sizes are
+ * all resized to the median width and height first */
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{Result, eyre};
+use image::{DynamicImage, Rgb, RgbImage, imageops::FilterType};
+
+/* The images in `dir` and the size they will be resized to. Only the headers are read here */
+fn scan(dir: &Path) -> Result<(Vec<PathBuf>, u32, u32)> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+
+    let mut images = Vec::new();
+    let mut widths = Vec::new();
+    let mut heights = Vec::new();
+    for path in paths {
+        if let Ok((w, h)) = image::image_dimensions(&path) {
+            images.push(path);
+            widths.push(w);
+            heights.push(h);
+        }
+    }
+    if images.is_empty() {
+        return Err(eyre!("No images found in {}", dir.display()));
+    }
+    widths.sort_unstable();
+    heights.sort_unstable();
+    Ok((images, widths[widths.len() / 2], heights[heights.len() / 2]))
+}
+
+/* Decodes every image and hands it to `f` at the common size, one at a time so only a single
+ * image is in memory */
+fn for_each_image(dir: &Path, mut f: impl FnMut(&RgbImage)) -> Result<(u32, u32, usize)> {
+    let (paths, width, height) = scan(dir)?;
+    let mut count = 0;
+    for (i, path) in paths.iter().enumerate() {
+        /* Decoding large images takes a while, so show how far along we are */
+        eprint!("\rLoading images {}/{}", i + 1, paths.len());
+        let _ = std::io::stderr().flush();
+        /* The header was fine but the data may not be */
+        let Ok(img) = image::open(path) else {
+            continue;
+        };
+        let img = if img.width() == width && img.height() == height {
+            img.to_rgb8()
+        } else {
+            img.resize_exact(width, height, FilterType::Triangle)
+                .to_rgb8()
+        };
+        f(&img);
+        count += 1;
+    }
+    eprintln!();
+    if count == 0 {
+        return Err(eyre!(
+            "None of the images in {} could be decoded",
+            dir.display()
+        ));
+    }
+    Ok((width, height, count))
+}
+
+/// The per-pixel mean of every image in `dir`
+pub fn average(dir: &Path) -> Result<DynamicImage> {
+    let mut sums: Vec<u64> = Vec::new();
+    let (width, height, count) = for_each_image(dir, |img| {
+        if sums.is_empty() {
+            sums = vec![0; img.as_raw().len()];
+        }
+        for (sum, &v) in sums.iter_mut().zip(img.as_raw()) {
+            *sum += v as u64;
+        }
+    })?;
+
+    let mut out = RgbImage::new(width, height);
+    for (p, sum) in out.pixels_mut().zip(sums.chunks_exact(3)) {
+        *p = Rgb([0, 1, 2].map(|c| (sum[c] as f64 / count as f64).round() as u8));
+    }
+    Ok(DynamicImage::ImageRgb8(out))
+}
+
+/// The per-pixel standard deviation of every image in `dir`, stretched so the largest one is 255
+pub fn std_dev(dir: &Path) -> Result<DynamicImage> {
+    let mut sums: Vec<f64> = Vec::new();
+    let mut squares: Vec<f64> = Vec::new();
+    let (width, height, count) = for_each_image(dir, |img| {
+        if sums.is_empty() {
+            sums = vec![0.0; img.as_raw().len()];
+            squares = vec![0.0; img.as_raw().len()];
+        }
+        for ((sum, square), &v) in sums.iter_mut().zip(squares.iter_mut()).zip(img.as_raw()) {
+            *sum += v as f64;
+            *square += v as f64 * v as f64;
+        }
+    })?;
+
+    let n = count as f64;
+    let deviations: Vec<f64> = sums
+        .iter()
+        .zip(&squares)
+        .map(|(sum, square)| (square / n - (sum / n).powi(2)).max(0.0).sqrt())
+        .collect();
+    /* Identical images have no deviation at all, keep them black instead of dividing by 0 */
+    let max = deviations
+        .iter()
+        .copied()
+        .fold(0.0, f64::max)
+        .max(f64::EPSILON);
+
+    let mut out = RgbImage::new(width, height);
+    for (p, dev) in out.pixels_mut().zip(deviations.chunks_exact(3)) {
+        *p = Rgb([0, 1, 2].map(|c| (dev[c] / max * 255.0).round() as u8));
+    }
+    Ok(DynamicImage::ImageRgb8(out))
+}
@@ -0,0 +1,130 @@
+/* Per channel statistics of an image, e.g. to check the exposure of a photo */
+use image::{DynamicImage, GenericImageView};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint::Length, Rect},
+    style::{Color, Style},
+    widgets::{Block, Clear, Row, Table, Widget},
+};
+
+const NAMES: [&str; 4] = ["R", "G", "B", "A"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChannelStats {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: u8,
+    pub max: u8,
+    /* Share of the pixels at 0 or 255, in percent */
+    pub clipped: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageStats {
+    pub channels: [ChannelStats; 4],
+    /* Whether the image has an alpha channel, the A row is left out if not */
+    pub has_alpha: bool,
+}
+
+/// Mean, standard deviation, minimum, maximum and clipping of the R, G, B and A channels
+pub fn compute_stats(img: &DynamicImage) -> ImageStats {
+    let mut counts = [[0u64; 256]; 4];
+    for (_, _, pixel) in img.pixels() {
+        for (channel, count) in counts.iter_mut().enumerate() {
+            count[pixel[channel] as usize] += 1;
+        }
+    }
+
+    let total = (img.width() as u64 * img.height() as u64).max(1) as f64;
+    let channels = counts.map(|count| {
+        let mean = (0..256).map(|v| v as f64 * count[v] as f64).sum::<f64>() / total;
+        let variance = (0..256)
+            .map(|v| (v as f64 - mean).powi(2) * count[v] as f64)
+            .sum::<f64>()
+            / total;
+        ChannelStats {
+            mean,
+            std_dev: variance.sqrt(),
+            min: (0..=255).find(|&v| count[v as usize] > 0).unwrap_or(0),
+            max: (0..=255)
+                .rev()
+                .find(|&v| count[v as usize] > 0)
+                .unwrap_or(0),
+            clipped: (count[0] + count[255]) as f64 / total * 100.0,
+        }
+    });
+    ImageStats {
+        channels,
+        has_alpha: img.color().has_alpha(),
+    }
+}
+
+impl Widget for &ImageStats {
+    /* A table in the middle of `area`, one row per channel */
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let shown = if self.has_alpha { 4 } else { 3 };
+        let width = 56.min(area.width);
+        let height = (shown as u16 + 3).min(area.height);
+        let overlay = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+        let rows = self
+            .channels
+            .iter()
+            .zip(NAMES)
+            .take(shown)
+            .map(|(c, name)| {
+                Row::new([
+                    name.to_string(),
+                    format!("{:.1}", c.mean),
+                    format!("{:.1}", c.std_dev),
+                    c.min.to_string(),
+                    c.max.to_string(),
+                    format!("{:.2}%", c.clipped),
+                ])
+            });
+        let header = Row::new(["", "Mean", "Std dev", "Min", "Max", "Clipped"])
+            .style(Style::new().fg(Color::Yellow));
+        let table = Table::new(
+            rows,
+            [
+                Length(3),
+                Length(8),
+                Length(8),
+                Length(5),
+                Length(5),
+                Length(8),
+            ],
+        )
+        .header(header)
+        .block(
+            Block::bordered()
+                .title(" Statistics ")
+                .border_style(Style::new().fg(Color::Yellow)),
+        );
+        Clear.render(overlay, buf);
+        table.render(overlay, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn pure_red() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(2, 2, Rgb([255, 0, 0])));
+        let stats = compute_stats(&img);
+        let [r, g, b, _] = stats.channels;
+        assert_eq!((r.mean, r.std_dev, r.min, r.max), (255.0, 0.0, 255, 255));
+        assert_eq!((g.mean, g.std_dev), (0.0, 0.0));
+        assert_eq!((b.mean, b.std_dev), (0.0, 0.0));
+        /* Every pixel is at 0 or 255 in all three channels, and `clipped` is a percentage */
+        assert!([r, g, b].iter().all(|c| c.clipped == 100.0));
+        assert!(!stats.has_alpha);
+    }
+}
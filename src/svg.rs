@@ -0,0 +1,61 @@
+/* Rasterizing SVG images with resvg, they are drawn at the size of the terminal so they stay sharp
+ * without wasting memory on pixels that are never shown */
+use std::path::Path;
+
+use image::{
+    DynamicImage, ImageError, ImageResult, Rgba, RgbaImage,
+    error::{DecodingError, ImageFormatHint},
+};
+use ratatui::crossterm::terminal;
+use resvg::{
+    tiny_skia::{Pixmap, Transform},
+    usvg::{Options, Tree},
+};
+
+fn decoding_error(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> ImageError {
+    ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Name("SVG".to_string()),
+        err,
+    ))
+}
+
+/* Half-block rendering fits two pixels in every cell */
+fn terminal_pixels() -> Option<(u32, u32)> {
+    let (cols, rows) = terminal::size().ok()?;
+    (cols > 0 && rows > 0).then_some((cols as u32, rows as u32 * 2))
+}
+
+/// Rasterizes the SVG at `path` so it fits the terminal, or at its own size when there is no
+/// terminal
+pub fn decode(path: &Path) -> ImageResult<DynamicImage> {
+    let data = std::fs::read(path)?;
+    let mut options = Options {
+        resources_dir: path.parent().map(Path::to_path_buf),
+        ..Options::default()
+    };
+    options.fontdb_mut().load_system_fonts();
+    let tree = Tree::from_data(&data, &options).map_err(decoding_error)?;
+
+    let size = tree.size();
+    let scale = match terminal_pixels() {
+        Some((width, height)) => (width as f32 / size.width()).min(height as f32 / size.height()),
+        None => 1.0,
+    };
+    let width = (size.width() * scale).round().max(1.0) as u32;
+    let height = (size.height() * scale).round().max(1.0) as u32;
+    let mut pixmap =
+        Pixmap::new(width, height).ok_or_else(|| decoding_error("SVG is too large"))?;
+    resvg::render(
+        &tree,
+        Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    /* tiny-skia stores premultiplied alpha, the image crate expects it straight */
+    let mut out = RgbaImage::new(width, height);
+    for (p, src) in out.pixels_mut().zip(pixmap.pixels()) {
+        let c = src.demultiply();
+        *p = Rgba([c.red(), c.green(), c.blue(), c.alpha()]);
+    }
+    Ok(DynamicImage::ImageRgba8(out))
+}
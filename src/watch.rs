@@ -0,0 +1,50 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+#[derive(Debug, PartialEq)]
+pub enum WatchEvent {
+    Changed,
+}
+
+/* Watches a single file for changes. The parent directory is watched instead of the file
+ * itself, so atomic replaces (write to a temp file, then rename) are picked up too */
+#[derive(Debug)]
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher,
+    /* As it was given, not canonicalized */
+    pub path: PathBuf,
+    pub events: Receiver<WatchEvent>,
+}
+
+impl FileWatcher {
+    pub fn new(path: &Path) -> notify::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let target: PathBuf = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let file_name = target.file_name().map(|n| n.to_os_string());
+
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+                let Ok(event) = res else { return };
+                let relevant = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                    && event
+                        .paths
+                        .iter()
+                        .any(|p| p.file_name().map(|n| n.to_os_string()) == file_name);
+                if relevant {
+                    let _ = tx.send(WatchEvent::Changed);
+                }
+            })?;
+
+        let dir = target.parent().unwrap_or(Path::new("."));
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            path: path.to_path_buf(),
+            events: rx,
+        })
+    }
+}
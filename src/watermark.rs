@@ -0,0 +1,191 @@
+/* Text stamped over the image, as big letters in ASCII art and as plain text in the viewer */
+use charcoal_core::{AsciiCell, AsciiGrid};
+use image::Rgba;
+use ratatui::{buffer::Buffer, layout::Rect, style::Color, text::Text, widgets::Widget};
+
+/* Rows of every letter, `#` is a filled cell. Letters are only as wide as they need to be */
+const GLYPH_HEIGHT: usize = 5;
+const GLYPHS: &[(char, [&str; GLYPH_HEIGHT])] = &[
+    ('A', [" ## ", "#  #", "####", "#  #", "#  #"]),
+    ('B', ["### ", "#  #", "### ", "#  #", "### "]),
+    ('C', [" ###", "#   ", "#   ", "#   ", " ###"]),
+    ('D', ["### ", "#  #", "#  #", "#  #", "### "]),
+    ('E', ["####", "#   ", "### ", "#   ", "####"]),
+    ('F', ["####", "#   ", "### ", "#   ", "#   "]),
+    ('G', [" ###", "#   ", "# ##", "#  #", " ###"]),
+    ('H', ["#  #", "#  #", "####", "#  #", "#  #"]),
+    ('I', ["###", " # ", " # ", " # ", "###"]),
+    ('J', ["  ##", "   #", "   #", "#  #", " ## "]),
+    ('K', ["#  #", "# # ", "##  ", "# # ", "#  #"]),
+    ('L', ["#   ", "#   ", "#   ", "#   ", "####"]),
+    ('M', ["#   #", "## ##", "# # #", "#   #", "#   #"]),
+    ('N', ["#   #", "##  #", "# # #", "#  ##", "#   #"]),
+    ('O', [" ## ", "#  #", "#  #", "#  #", " ## "]),
+    ('P', ["### ", "#  #", "### ", "#   ", "#   "]),
+    ('Q', [" ## ", "#  #", "#  #", "# # ", " # #"]),
+    ('R', ["### ", "#  #", "### ", "# # ", "#  #"]),
+    ('S', [" ###", "#   ", " ## ", "   #", "### "]),
+    ('T', ["#####", "  #  ", "  #  ", "  #  ", "  #  "]),
+    ('U', ["#  #", "#  #", "#  #", "#  #", " ## "]),
+    ('V', ["#   #", "#   #", "#   #", " # # ", "  #  "]),
+    ('W', ["#   #", "#   #", "# # #", "## ##", "#   #"]),
+    ('X', ["#   #", " # # ", "  #  ", " # # ", "#   #"]),
+    ('Y', ["#   #", " # # ", "  #  ", "  #  ", "  #  "]),
+    ('Z', ["####", "   #", "  # ", " #  ", "####"]),
+    ('0', [" ## ", "#  #", "# ##", "## #", " ## "]),
+    ('1', [" # ", "## ", " # ", " # ", "###"]),
+    ('2', ["### ", "   #", " ## ", "#   ", "####"]),
+    ('3', ["### ", "   #", " ## ", "   #", "### "]),
+    ('4', ["#  #", "#  #", "####", "   #", "   #"]),
+    ('5', ["####", "#   ", "### ", "   #", "### "]),
+    ('6', [" ## ", "#   ", "### ", "#  #", " ## "]),
+    ('7', ["####", "   #", "  # ", " #  ", " #  "]),
+    ('8', [" ## ", "#  #", " ## ", "#  #", " ## "]),
+    ('9', [" ## ", "#  #", " ###", "   #", " ## "]),
+    (' ', ["  ", "  ", "  ", "  ", "  "]),
+    ('.', [" ", " ", " ", " ", "#"]),
+    (',', ["  ", "  ", "  ", " #", "# "]),
+    (':', [" ", "#", " ", "#", " "]),
+    ('!', ["#", "#", "#", " ", "#"]),
+    ('\'', ["#", "#", " ", " ", " "]),
+    ('-', ["   ", "   ", "###", "   ", "   "]),
+    ('_', ["   ", "   ", "   ", "   ", "###"]),
+    ('/', ["   #", "  # ", " #  ", "#   ", "#   "]),
+    ('@', [" ###", "#  #", "# ##", "#   ", " ###"]),
+    ('?', ["### ", "   #", " ## ", "    ", " #  "]),
+];
+
+/// Corner or middle of the image the watermark goes into
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    Center,
+}
+
+impl WatermarkPosition {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "top-left" => Ok(WatermarkPosition::TopLeft),
+            "top-right" => Ok(WatermarkPosition::TopRight),
+            "bottom-left" => Ok(WatermarkPosition::BottomLeft),
+            "bottom-right" => Ok(WatermarkPosition::BottomRight),
+            "center" => Ok(WatermarkPosition::Center),
+            _ => Err(format!(
+                "Unknown position: {} (expected top-left, top-right, bottom-left, bottom-right \
+                 or center)",
+                s
+            )),
+        }
+    }
+
+    /* Top left corner of a `width`x`height` box inside `outer_width`x`outer_height`. A box
+     * wider than that starts at the left edge and is clipped on the right */
+    fn origin(
+        &self,
+        (width, height): (u32, u32),
+        (outer_width, outer_height): (u32, u32),
+    ) -> (u32, u32) {
+        let (free_x, free_y) = (
+            outer_width.saturating_sub(width),
+            outer_height.saturating_sub(height),
+        );
+        match self {
+            WatermarkPosition::TopLeft => (0, 0),
+            WatermarkPosition::TopRight => (free_x, 0),
+            WatermarkPosition::BottomLeft => (0, free_y),
+            WatermarkPosition::BottomRight => (free_x, free_y),
+            WatermarkPosition::Center => (free_x / 2, free_y / 2),
+        }
+    }
+}
+
+/* Letters missing from the table are shown as ? */
+fn glyph(c: char) -> &'static [&'static str; GLYPH_HEIGHT] {
+    let find = |c| GLYPHS.iter().find(|(g, _)| *g == c).map(|(_, rows)| rows);
+    find(c.to_ascii_uppercase())
+        .or_else(|| find('?'))
+        .expect("? is in the table")
+}
+
+/* The big letters of `text` as rows of cells, one empty column between letters */
+fn banner(text: &str) -> Vec<String> {
+    let mut rows = vec![String::new(); GLYPH_HEIGHT];
+    for (i, c) in text.chars().enumerate() {
+        for (row, glyph_row) in rows.iter_mut().zip(glyph(c)) {
+            if i > 0 {
+                row.push(' ');
+            }
+            row.push_str(glyph_row);
+        }
+    }
+    rows
+}
+
+/// Writes `text` over the characters of `grid` at `position`. The letters are drawn with `#`
+/// on a blank box so they can be read on any image, grids too short for them get the text
+/// itself. Whatever doesn't fit the width is cut off
+pub fn stamp(grid: &mut AsciiGrid, text: &str, position: WatermarkPosition) {
+    let rows = if grid.height as usize >= GLYPH_HEIGHT {
+        banner(text)
+    } else {
+        vec![text.to_string()]
+    };
+    let width = rows
+        .iter()
+        .map(|row| row.chars().count())
+        .max()
+        .unwrap_or(0) as u32;
+    let (x0, y0) = position.origin((width, rows.len() as u32), (grid.width, grid.height));
+    for (dy, row) in rows.iter().enumerate() {
+        let y = y0 + dy as u32;
+        if y >= grid.height {
+            break;
+        }
+        for (dx, c) in row.chars().enumerate() {
+            let x = x0 + dx as u32;
+            if x >= grid.width {
+                break;
+            }
+            grid.cells[(y * grid.width + x) as usize] = match c {
+                ' ' => AsciiCell {
+                    ch: ' ',
+                    color: None,
+                },
+                c => AsciiCell {
+                    ch: c,
+                    color: Some(Rgba([255, 255, 255, 255])),
+                },
+            };
+        }
+    }
+}
+
+/* The watermark in the viewer, drawn over the cells the image covers */
+pub struct Watermark<'a> {
+    pub text: &'a str,
+    pub position: WatermarkPosition,
+}
+
+impl Widget for Watermark<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.is_empty() {
+            return;
+        }
+        let width = self.text.chars().count() as u32;
+        let (x, y) = self
+            .position
+            .origin((width, 1), (area.width as u32, area.height as u32));
+        /* Clipped to the image, not just the screen */
+        let text = Rect {
+            x: area.x + x as u16,
+            y: area.y + y as u16,
+            width: (width as u16).min(area.width - x as u16),
+            height: 1,
+        };
+        Text::from(self.text).style(Color::White).render(text, buf);
+    }
+}
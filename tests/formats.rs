@@ -0,0 +1,79 @@
+//! Opens the AVIF and HEIC fixtures with the charcoal binary. Both are 8x8, red on the left
+//! half and blue on the right, so `--palette 2` prints one of each.
+
+use std::{path::Path, process::Output};
+
+fn palette(fixture: &str) -> Output {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(fixture);
+    std::process::Command::new(env!("CARGO_BIN_EXE_charcoal"))
+        .args(["--palette", "2"])
+        .arg(path)
+        .output()
+        .expect("charcoal runs")
+}
+
+#[cfg(any(feature = "avif", feature = "heif"))]
+fn assert_red_and_blue(output: Output) {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let colors: Vec<[u8; 3]> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let hex = line.trim().trim_start_matches('#');
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+            [channel(0), channel(2), channel(4)]
+        })
+        .collect();
+    /* Both are lossy, so only close to pure red and blue */
+    assert!(
+        colors.iter().any(|&[r, _, b]| r > 200 && b < 50),
+        "{:?}",
+        colors
+    );
+    assert!(
+        colors.iter().any(|&[r, _, b]| b > 200 && r < 50),
+        "{:?}",
+        colors
+    );
+}
+
+#[cfg(not(all(feature = "avif", feature = "heif")))]
+fn assert_needs_feature(output: Output, feature: &str) {
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!("built without the {} feature", feature)),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+#[cfg(feature = "avif")]
+fn decodes_avif() {
+    assert_red_and_blue(palette("tiny.avif"));
+}
+
+#[test]
+#[cfg(not(feature = "avif"))]
+fn avif_needs_the_avif_feature() {
+    assert_needs_feature(palette("tiny.avif"), "avif");
+}
+
+#[test]
+#[cfg(feature = "heif")]
+fn decodes_heic() {
+    assert_red_and_blue(palette("tiny.heic"));
+}
+
+#[test]
+#[cfg(not(feature = "heif"))]
+fn heic_needs_the_heif_feature() {
+    assert_needs_feature(palette("tiny.heic"), "heif");
+}